@@ -0,0 +1,599 @@
+use super::index_file::IndexDocument;
+use super::parser::AgentLibraryParser;
+use super::types::{AgentIndex, AgentLibrary, McpEndpoint};
+use super::{cache, storage};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// バンドル内のマニフェストのファイル名
+pub const MANIFEST_FILE: &str = "librarian-bundle.json";
+// マニフェストの形式のバージョン
+const MANIFEST_VERSION: u32 = 1;
+// 展開後の合計サイズの上限（圧縮爆弾対策）
+const MAX_BUNDLE_BYTES: u64 = 64 << 20;
+// 既存のインデックスに統合するエンドポイント以外のキー
+const MERGED_SECTIONS: [&str; 4] = ["include", "exclude", "resources", "lint"];
+
+/// バンドルの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BundleFormat {
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    #[serde(rename = "zip")]
+    Zip,
+}
+
+impl BundleFormat {
+    /// 拡張子（`.tar.gz` / `.tgz` / `.zip`）から形式を判定する
+    #[must_use] pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// バンドルに含まれるファイルとチェックサムの一覧
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub manifest_version: u32,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    // RFC 3339
+    pub created_at: String,
+    pub files: Vec<BundleFile>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleFile {
+    // `.agent_library` からの相対パス（`/` 区切り）
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// インポート前の確認結果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportPreview {
+    pub manifest: BundleManifest,
+    // バンドルのエンドポイントID
+    pub endpoints: Vec<String>,
+    // 既存のエンドポイントと同じID（上書きする場合は置き換える）
+    pub id_conflicts: Vec<String>,
+    // 既存の別名と重なるID・別名（上書きする場合もインポートできない）
+    pub alias_conflicts: Vec<String>,
+    // 既存のファイルと内容が異なるファイル
+    pub file_conflicts: Vec<String>,
+}
+
+/// インポートの結果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub endpoints: Vec<String>,
+    pub files: Vec<String>,
+    // 置き換えたエンドポイントID
+    pub replaced: Vec<String>,
+}
+
+// 読み込んで検証したバンドル
+struct Bundle {
+    manifest: BundleManifest,
+    files: BTreeMap<String, Vec<u8>>,
+    index: AgentIndex,
+}
+
+/// ライブラリをバンドルに書き出す
+///
+/// `agent_index.yml`、プロンプト、インクルード、ロケール別ファイル、添付ファイルとリソースを含める。
+/// `extends` の継承元のファイルは含めない
+pub fn export(repo_path: &Path, output: &Path, format: Option<BundleFormat>) -> Result<BundleManifest> {
+    let format = format
+        .or_else(|| BundleFormat::from_path(output))
+        .ok_or_else(|| anyhow::anyhow!("Unknown bundle format for {} (use .tar.gz or .zip)", output.display()))?;
+    let library = AgentLibraryParser::parse(repo_path)?;
+    if let Some(extends) = &library.index.extends {
+        tracing::warn!("Exporting library that extends '{extends}': files of the base library are not included");
+    }
+
+    let mut files = BTreeMap::new();
+    for path in library_files(&library)? {
+        let content = fs::read(library.base_path.join(&path))
+            .with_context(|| format!("Failed to read {path}"))?;
+        files.insert(path, content);
+    }
+
+    let manifest = BundleManifest {
+        manifest_version: MANIFEST_VERSION,
+        name: library.index.name.clone(),
+        version: library.index.version,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        files: files
+            .iter()
+            .map(|(path, content)| BundleFile {
+                path: path.clone(),
+                sha256: storage::content_hash(content),
+                size: content.len() as u64,
+            })
+            .collect(),
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+
+    let file = fs::File::create(output).with_context(|| format!("Failed to create {}", output.display()))?;
+    let entries = std::iter::once((MANIFEST_FILE, manifest_json.as_slice()))
+        .chain(files.iter().map(|(path, content)| (path.as_str(), content.as_slice())));
+    match format {
+        BundleFormat::TarGz => write_tar_gz(file, entries)?,
+        BundleFormat::Zip => write_zip(file, entries)?,
+    }
+
+    tracing::info!("Exported {} files to {}", manifest.files.len(), output.display());
+    Ok(manifest)
+}
+
+/// バンドルを検証し、インポート先のライブラリとの重複を調べる（ファイルは書き込まない）
+pub fn inspect(bundle_path: &Path, repo_path: &Path) -> Result<ImportPreview> {
+    let bundle = read_bundle(bundle_path)?;
+    Ok(preview(&bundle, &repo_path.join(".agent_library")))
+}
+
+/// バンドルを検証してリポジトリの `.agent_library` に展開する
+///
+/// 既存のライブラリがある場合はエンドポイントと `include`・`exclude`・`resources`・`lint` を `agent_index.yml` に統合する。
+/// ID やファイルが重複する場合は `overwrite` を指定しない限りエラーにし、既存の別名と重なる場合は常にエラーにする。
+/// 途中で失敗した場合は書き込んだファイルを元に戻す
+pub fn import(bundle_path: &Path, repo_path: &Path, overwrite: bool) -> Result<ImportReport> {
+    if !repo_path.is_dir() {
+        return Err(anyhow::anyhow!("Repository {} not found", repo_path.display()));
+    }
+    let bundle = read_bundle(bundle_path)?;
+    let agent_lib_path = repo_path.join(".agent_library");
+    let preview = preview(&bundle, &agent_lib_path);
+
+    if !preview.alias_conflicts.is_empty() || (!overwrite && (!preview.id_conflicts.is_empty() || !preview.file_conflicts.is_empty())) {
+        let conflicts: Vec<String> = preview
+            .alias_conflicts
+            .iter()
+            .map(|name| format!("alias '{name}'"))
+            .chain(preview.id_conflicts.iter().map(|id| format!("id '{id}'")))
+            .chain(preview.file_conflicts.iter().map(|file| format!("file '{file}'")))
+            .collect();
+        return Err(anyhow::anyhow!("Bundle conflicts with the existing library: {}", conflicts.join(", ")));
+    }
+
+    // 既存のインデックスへの統合はファイルを書き込む前に済ませる
+    let index_path = agent_lib_path.join("agent_index.yml");
+    let mut report = ImportReport {
+        endpoints: preview.endpoints,
+        ..Default::default()
+    };
+    let document = if index_path.is_file() {
+        let mut document = IndexDocument::load(&index_path)?;
+        let incoming: serde_yaml::Value = serde_yaml::from_slice(&bundle.files["agent_index.yml"])?;
+        let endpoints = incoming
+            .get("mcp_endpoints")
+            .and_then(serde_yaml::Value::as_sequence)
+            .cloned()
+            .unwrap_or_default();
+        report.replaced = document.upsert_endpoints(endpoints)?;
+        for section in MERGED_SECTIONS {
+            if let Some(value) = incoming.get(section) {
+                document.merge_section(section, value)?;
+            }
+        }
+        document.index()?;
+        Some(document)
+    } else {
+        None
+    };
+
+    let mut written: Vec<(PathBuf, Option<Vec<u8>>)> = Vec::new();
+    let result = (|| -> Result<()> {
+        for (path, content) in &bundle.files {
+            if document.is_some() && path == "agent_index.yml" {
+                continue;
+            }
+            let target = agent_lib_path.join(path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            written.push((target.clone(), fs::read(&target).ok()));
+            storage::write_atomic(&target, content)?;
+            report.files.push(path.clone());
+        }
+        if let Some(document) = &document {
+            document.save()?;
+            report.files.push("agent_index.yml".to_string());
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        // 書き込んだファイルを元に戻す
+        for (target, previous) in written.iter().rev() {
+            let _ = match previous {
+                Some(content) => storage::write_atomic(target, content),
+                None => fs::remove_file(target).map_err(Into::into),
+            };
+        }
+        return Err(e);
+    }
+
+    AgentLibraryParser::invalidate(&agent_lib_path);
+    tracing::info!("Imported {} files into {}", report.files.len(), agent_lib_path.display());
+    Ok(report)
+}
+
+// ライブラリを構成するファイル（`.agent_library` からの相対パス）
+fn library_files(library: &AgentLibrary) -> Result<BTreeSet<String>> {
+    let root = library
+        .base_path
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", library.base_path.display()))?;
+
+    let mut paths: Vec<PathBuf> = vec![library.base_path.join("agent_index.yml")];
+    for prompt in &library.prompts {
+        paths.push(prompt.file_path.clone());
+        paths.extend(prompt.included_files.iter().cloned());
+        for variant in &prompt.variants {
+            paths.push(variant.file_path.clone());
+            paths.extend(variant.included_files.iter().cloned());
+        }
+        paths.extend(prompt.attachments.iter().map(|a| a.file_path.clone()));
+    }
+    paths.extend(library.resources.iter().map(|r| r.file_path.clone()));
+
+    Ok(paths
+        .iter()
+        .filter_map(|path| {
+            let canonical = cache::canonicalize_lenient(path);
+            let relative = canonical.strip_prefix(&root).ok()?;
+            Some(relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+        })
+        .collect())
+}
+
+fn write_tar_gz<'a>(file: fs::File, entries: impl Iterator<Item = (&'a str, &'a [u8])>) -> Result<()> {
+    let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    for (path, content) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        builder.append_data(&mut header, path, content)?;
+    }
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+fn write_zip<'a>(file: fs::File, entries: impl Iterator<Item = (&'a str, &'a [u8])>) -> Result<()> {
+    let mut writer = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (path, content) in entries {
+        writer.start_file(path, options)?;
+        writer.write_all(content)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+// バンドルを読み込み、マニフェストのチェックサムとファイルの一覧を検証する
+fn read_bundle(bundle_path: &Path) -> Result<Bundle> {
+    let mut magic = [0u8; 4];
+    let read = fs::File::open(bundle_path)
+        .and_then(|mut file| file.read(&mut magic))
+        .with_context(|| format!("Failed to open {}", bundle_path.display()))?;
+    let mut entries = match &magic[..read] {
+        [b'P', b'K', 3, 4] => read_zip(bundle_path)?,
+        [0x1f, 0x8b, ..] => read_tar_gz(bundle_path)?,
+        _ => return Err(anyhow::anyhow!("{} is not a .tar.gz or .zip bundle", bundle_path.display())),
+    };
+
+    let manifest_json = entries
+        .remove(MANIFEST_FILE)
+        .ok_or_else(|| anyhow::anyhow!("Bundle has no {MANIFEST_FILE}"))?;
+    let manifest: BundleManifest = serde_json::from_slice(&manifest_json).context("Invalid bundle manifest")?;
+    if manifest.manifest_version > MANIFEST_VERSION {
+        return Err(anyhow::anyhow!("Unsupported bundle manifest version {}", manifest.manifest_version));
+    }
+
+    for file in &manifest.files {
+        let content = entries
+            .get(&file.path)
+            .ok_or_else(|| anyhow::anyhow!("File '{}' listed in the manifest is missing", file.path))?;
+        if storage::content_hash(content) != file.sha256.to_lowercase() {
+            return Err(anyhow::anyhow!("Checksum mismatch for '{}'", file.path));
+        }
+    }
+    if let Some(extra) = entries.keys().find(|path| !manifest.files.iter().any(|f| &f.path == *path)) {
+        return Err(anyhow::anyhow!("File '{extra}' is not listed in the manifest"));
+    }
+
+    let index_source = entries
+        .get("agent_index.yml")
+        .ok_or_else(|| anyhow::anyhow!("Bundle has no agent_index.yml"))?;
+    let index: AgentIndex = serde_yaml::from_slice(index_source).context("Invalid agent_index.yml in bundle")?;
+
+    Ok(Bundle { manifest, files: entries, index })
+}
+
+fn read_tar_gz(bundle_path: &Path) -> Result<BTreeMap<String, Vec<u8>>> {
+    let file = fs::File::open(bundle_path)?;
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    let mut entries = BTreeMap::new();
+    let mut total = 0;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type().is_dir() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().to_string();
+        if !entry.header().entry_type().is_file() {
+            return Err(anyhow::anyhow!("Unsupported entry '{name}' in bundle"));
+        }
+        let content = read_limited(&mut entry, &mut total)?;
+        entries.insert(entry_name(&name)?, content);
+    }
+
+    Ok(entries)
+}
+
+fn read_zip(bundle_path: &Path) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(bundle_path)?)?;
+    let mut entries = BTreeMap::new();
+    let mut total = 0;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let content = read_limited(&mut entry, &mut total)?;
+        entries.insert(entry_name(&name)?, content);
+    }
+
+    Ok(entries)
+}
+
+fn read_limited(reader: &mut impl Read, total: &mut u64) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    reader.take(MAX_BUNDLE_BYTES - *total + 1).read_to_end(&mut content)?;
+    *total += content.len() as u64;
+    if *total > MAX_BUNDLE_BYTES {
+        return Err(anyhow::anyhow!("Bundle exceeds {} MiB", MAX_BUNDLE_BYTES >> 20));
+    }
+    Ok(content)
+}
+
+// 展開先のパスとして安全な相対パスに正規化する（絶対パスや `..` は拒否）
+fn entry_name(name: &str) -> Result<String> {
+    let path = Path::new(name);
+    let mut segments = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(segment) => segments.push(segment.to_string_lossy().to_string()),
+            Component::CurDir => {}
+            _ => return Err(anyhow::anyhow!("Unsafe path '{name}' in bundle")),
+        }
+    }
+    if segments.is_empty() {
+        return Err(anyhow::anyhow!("Empty path in bundle"));
+    }
+    Ok(segments.join("/"))
+}
+
+fn preview(bundle: &Bundle, agent_lib_path: &Path) -> ImportPreview {
+    let existing: Vec<McpEndpoint> = fs::read_to_string(agent_lib_path.join("agent_index.yml"))
+        .ok()
+        .and_then(|content| serde_yaml::from_str::<AgentIndex>(&content).ok())
+        .map(|index| index.mcp_endpoints)
+        .unwrap_or_default();
+    let incoming_ids: BTreeSet<&str> = bundle.index.mcp_endpoints.iter().map(|e| e.id.as_str()).collect();
+
+    let id_conflicts = bundle
+        .index
+        .mcp_endpoints
+        .iter()
+        .filter(|e| existing.iter().any(|local| local.id == e.id))
+        .map(|e| e.id.clone())
+        .collect();
+    // 置き換えられないエンドポイントのIDや別名と重なるもの
+    let kept: BTreeSet<&str> = existing
+        .iter()
+        .filter(|local| !incoming_ids.contains(local.id.as_str()))
+        .flat_map(|local| std::iter::once(&local.id).chain(&local.aliases))
+        .map(String::as_str)
+        .collect();
+    let alias_conflicts = bundle
+        .index
+        .mcp_endpoints
+        .iter()
+        .flat_map(|e| std::iter::once(&e.id).chain(&e.aliases))
+        .filter(|name| kept.contains(name.as_str()))
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let file_conflicts = bundle
+        .files
+        .iter()
+        .filter(|(path, _)| path.as_str() != "agent_index.yml")
+        .filter(|(path, content)| fs::read(agent_lib_path.join(path)).is_ok_and(|existing| existing != **content))
+        .map(|(path, _)| path.clone())
+        .collect();
+
+    ImportPreview {
+        manifest: bundle.manifest.clone(),
+        endpoints: bundle.index.mcp_endpoints.iter().map(|e| e.id.clone()).collect(),
+        id_conflicts,
+        alias_conflicts,
+        file_conflicts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_library(repo: &Path, index: &str, files: &[(&str, &str)]) {
+        let agent_lib_dir = repo.join(".agent_library");
+        fs::create_dir_all(&agent_lib_dir).unwrap();
+        fs::write(agent_lib_dir.join("agent_index.yml"), index).unwrap();
+        for (name, content) in files {
+            let path = agent_lib_dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    fn source_library(dir: &Path) -> PathBuf {
+        let repo = dir.join("source");
+        write_library(
+            &repo,
+            "name: Shared\nmcp_endpoints:\n  - id: review\n    label: Review\n    prompt_file: prompts/review.md\n    attachments: [schemas/review.json]\nresources:\n  - path: docs/guide.md\n    name: Guide\nlint:\n  max_prompt_chars: 4000\n",
+            &[
+                ("prompts/review.md", "# Review\n{{> shared/style.md}}"),
+                ("docs/guide.md", "# Guide"),
+                ("prompts/review.ja.md", "# レビュー"),
+                ("shared/style.md", "Style"),
+                ("schemas/review.json", "{}"),
+                ("notes.txt", "not referenced"),
+            ],
+        );
+        repo
+    }
+
+    #[test]
+    fn test_export_and_import_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = source_library(temp_dir.path());
+
+        for name in ["shared.tar.gz", "shared.zip"] {
+            let bundle = temp_dir.path().join(name);
+            let manifest = export(&source, &bundle, None).unwrap();
+            let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+            assert_eq!(
+                paths,
+                vec![
+                    "agent_index.yml",
+                    "docs/guide.md",
+                    "prompts/review.ja.md",
+                    "prompts/review.md",
+                    "schemas/review.json",
+                    "shared/style.md",
+                ]
+            );
+
+            let target = temp_dir.path().join(format!("target-{name}"));
+            fs::create_dir_all(&target).unwrap();
+            let report = import(&bundle, &target, false).unwrap();
+            assert_eq!(report.endpoints, vec!["review".to_string()]);
+
+            let library = AgentLibraryParser::parse(&target).unwrap();
+            assert_eq!(library.prompts[0].content, "# Review\nStyle");
+            assert_eq!(library.prompts[0].variants.len(), 1);
+            assert!(!target.join(".agent_library/notes.txt").exists());
+        }
+    }
+
+    #[test]
+    fn test_import_detects_conflicts_and_merges_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = source_library(temp_dir.path());
+        let bundle = temp_dir.path().join("shared.zip");
+        export(&source, &bundle, None).unwrap();
+
+        let target = temp_dir.path().join("target");
+        write_library(
+            &target,
+            "name: Local\nmcp_endpoints:\n  - id: commit\n    label: Commit\n    prompt_file: commit.md\n  - id: review\n    label: Local review\n    prompt_file: local_review.md\n# resources\nresources:\n  - docs/local.md\n",
+            &[
+                ("commit.md", "# Commit"),
+                ("local_review.md", "# Local review"),
+                ("docs/local.md", "# Local"),
+                ("shared/style.md", "Local style"),
+            ],
+        );
+
+        let preview = inspect(&bundle, &target).unwrap();
+        assert_eq!(preview.id_conflicts, vec!["review".to_string()]);
+        assert!(preview.alias_conflicts.is_empty());
+        assert_eq!(preview.file_conflicts, vec!["shared/style.md".to_string()]);
+
+        let error = import(&bundle, &target, false).unwrap_err().to_string();
+        assert!(error.contains("id 'review'") && error.contains("file 'shared/style.md'"), "{error}");
+
+        // 同じIDのエンドポイントだけを置き換え、リソースとリントの設定も統合する
+        let report = import(&bundle, &target, true).unwrap();
+        assert_eq!(report.replaced, vec!["review".to_string()]);
+        let library = AgentLibraryParser::parse(&target).unwrap();
+        let ids: Vec<&str> = library.prompts.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["commit", "review"]);
+        assert_eq!(library.find_prompt("review").unwrap().title, "Review");
+        assert_eq!(library.index.name.as_deref(), Some("Local"));
+        let resources: Vec<&str> = library.index.resources.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(resources, vec!["docs/local.md", "docs/guide.md"]);
+        assert_eq!(library.index.lint.max_prompt_chars, Some(4000));
+        let index = fs::read_to_string(target.join(".agent_library/agent_index.yml")).unwrap();
+        assert!(index.contains("# resources\nresources:\n  - docs/local.md\n"), "{index}");
+    }
+
+    #[test]
+    fn test_import_rejects_alias_conflicts_without_writing() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = source_library(temp_dir.path());
+        let bundle = temp_dir.path().join("shared.tar.gz");
+        export(&source, &bundle, None).unwrap();
+
+        // 別名と重なる場合は上書きする場合もインポートしない
+        let target = temp_dir.path().join("target");
+        let index = "mcp_endpoints:\n  - id: old\n    label: Old\n    prompt_file: old.md\n    aliases: [review]\n";
+        write_library(&target, index, &[("old.md", "# Old")]);
+        assert_eq!(inspect(&bundle, &target).unwrap().alias_conflicts, vec!["review".to_string()]);
+        let error = import(&bundle, &target, true).unwrap_err().to_string();
+        assert!(error.contains("alias 'review'"), "{error}");
+        assert_eq!(fs::read_to_string(target.join(".agent_library/agent_index.yml")).unwrap(), index);
+        assert!(!target.join(".agent_library/prompts").exists());
+
+        // インデックスを統合できない場合もファイルを書き込まない
+        let flow = temp_dir.path().join("flow");
+        write_library(&flow, "mcp_endpoints: [{id: commit, label: Commit, prompt_file: commit.md}]\n", &[("commit.md", "# Commit")]);
+        assert!(import(&bundle, &flow, true).is_err());
+        assert!(!flow.join(".agent_library/prompts").exists());
+    }
+
+    #[test]
+    fn test_import_rejects_tampered_bundle() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = source_library(temp_dir.path());
+        let bundle = temp_dir.path().join("shared.tar.gz");
+        export(&source, &bundle, None).unwrap();
+
+        // マニフェストはそのままでファイルの内容だけを書き換える
+        let mut entries = read_tar_gz(&bundle).unwrap();
+        entries.insert("shared/style.md".to_string(), b"Injected".to_vec());
+        let tampered = temp_dir.path().join("tampered.tar.gz");
+        let file = fs::File::create(&tampered).unwrap();
+        write_tar_gz(file, entries.iter().map(|(path, content)| (path.as_str(), content.as_slice()))).unwrap();
+
+        let target = temp_dir.path().join("target");
+        fs::create_dir_all(&target).unwrap();
+        let error = import(&tampered, &target, false).unwrap_err().to_string();
+        assert!(error.contains("Checksum mismatch for 'shared/style.md'"), "{error}");
+        assert!(!target.join(".agent_library").exists());
+
+        assert!(entry_name("../escape.md").is_err());
+        assert!(entry_name("/etc/passwd").is_err());
+        assert_eq!(entry_name("./prompts/a.md").unwrap(), "prompts/a.md");
+    }
+}
@@ -0,0 +1,170 @@
+use super::types::AgentLibrary;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+// ファイルの指紋（最終更新時刻とサイズ）。存在しないファイルは None
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fingerprint {
+    modified: SystemTime,
+    len: u64,
+}
+
+struct CacheEntry {
+    library: AgentLibrary,
+    // 正規化したライブラリのディレクトリ（extends の継承元を含む）
+    roots: Vec<PathBuf>,
+    fingerprints: HashMap<PathBuf, Option<Fingerprint>>,
+}
+
+// Agent Libraryキャッシュ（.agent_library のパスをキーとする）
+static AGENT_LIBRARY_CACHE: std::sync::LazyLock<Mutex<HashMap<PathBuf, CacheEntry>>> = std::sync::LazyLock::new(|| {
+    Mutex::new(HashMap::new())
+});
+
+/// キャッシュ済みのライブラリを返す（いずれかのファイルが変更されていれば None）
+pub fn lookup(agent_lib_path: &Path) -> Option<AgentLibrary> {
+    let cache = AGENT_LIBRARY_CACHE.lock().ok()?;
+    let entry = cache.get(agent_lib_path)?;
+
+    let unchanged = entry
+        .fingerprints
+        .iter()
+        .all(|(path, fingerprint)| fingerprint_of(path) == *fingerprint);
+
+    unchanged.then(|| entry.library.clone())
+}
+
+/// ライブラリをキャッシュに保存する
+///
+/// インデックス、プロンプト、インクルードしたファイルと、
+/// ファイルの追加・削除を検出するために各ライブラリ内のディレクトリの指紋を記録する
+pub fn store(agent_lib_path: &Path, library: &AgentLibrary, roots: &[PathBuf]) {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for root in roots {
+        paths.push(root.join("agent_index.yml"));
+        paths.extend(
+            WalkDir::new(root)
+                .into_iter()
+                .filter_map(std::result::Result::ok)
+                .filter(|entry| entry.file_type().is_dir())
+                .map(walkdir::DirEntry::into_path),
+        );
+    }
+    for prompt in &library.prompts {
+        paths.push(prompt.file_path.clone());
+        paths.extend(prompt.included_files.iter().cloned());
+        for variant in &prompt.variants {
+            paths.push(variant.file_path.clone());
+            paths.extend(variant.included_files.iter().cloned());
+        }
+    }
+
+    let fingerprints = paths
+        .into_iter()
+        .map(|path| {
+            let fingerprint = fingerprint_of(&path);
+            (path, fingerprint)
+        })
+        .collect();
+
+    if let Ok(mut cache) = AGENT_LIBRARY_CACHE.lock() {
+        cache.insert(
+            agent_lib_path.to_path_buf(),
+            CacheEntry {
+                library: library.clone(),
+                roots: roots.to_vec(),
+                fingerprints,
+            },
+        );
+    }
+}
+
+/// 部分的に更新したライブラリでキャッシュを置き換え、変更されたファイルの指紋だけを更新する
+///
+/// キャッシュにエントリが無い場合は何もせず `false` を返す（呼び出し側が `store` で登録し直す）
+pub fn refresh(agent_lib_path: &Path, library: &AgentLibrary, changed_paths: &[PathBuf]) -> bool {
+    let Ok(mut cache) = AGENT_LIBRARY_CACHE.lock() else {
+        return false;
+    };
+    let Some(entry) = cache.get_mut(agent_lib_path) else {
+        return false;
+    };
+
+    entry.library = library.clone();
+    for path in changed_paths {
+        entry.fingerprints.insert(path.clone(), fingerprint_of(path));
+        if let Some(parent) = path.parent() {
+            if entry.fingerprints.contains_key(parent) {
+                entry.fingerprints.insert(parent.to_path_buf(), fingerprint_of(parent));
+            }
+        }
+    }
+    // 読み直したプロンプトが新たにインクルードしたファイルやロケール別ファイルも追跡する
+    for prompt in &library.prompts {
+        let variants = prompt.variants.iter();
+        let paths = prompt
+            .included_files
+            .iter()
+            .chain(variants.clone().map(|v| &v.file_path))
+            .chain(variants.flat_map(|v| v.included_files.iter()));
+        for path in paths {
+            if !entry.fingerprints.contains_key(path) {
+                entry.fingerprints.insert(path.clone(), fingerprint_of(path));
+            }
+        }
+    }
+    true
+}
+
+/// 指定したパスに関係するキャッシュを破棄する
+///
+/// リポジトリ、`.agent_library`、またはその中のファイルのパスを受け付ける。
+/// 継承元のライブラリ内のパスを指定した場合は、継承しているライブラリのキャッシュも破棄する
+pub fn invalidate(path: &Path) {
+    let canonical = canonicalize_lenient(path);
+
+    if let Ok(mut cache) = AGENT_LIBRARY_CACHE.lock() {
+        cache.retain(|key, entry| {
+            let affected = key.starts_with(path)
+                || path.starts_with(key)
+                || entry
+                    .roots
+                    .iter()
+                    .any(|root| root.starts_with(&canonical) || canonical.starts_with(root));
+            if affected {
+                tracing::debug!("Invalidated agent library cache: {}", key.display());
+            }
+            !affected
+        });
+    }
+}
+
+/// キャッシュをすべて破棄する
+pub fn clear() {
+    if let Ok(mut cache) = AGENT_LIBRARY_CACHE.lock() {
+        cache.clear();
+    }
+}
+
+fn fingerprint_of(path: &Path) -> Option<Fingerprint> {
+    let metadata = fs::metadata(path).ok()?;
+    Some(Fingerprint {
+        modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        len: metadata.len(),
+    })
+}
+
+// 削除済みのファイルでも親ディレクトリを正規化して比較できるようにする
+pub(crate) fn canonicalize_lenient(path: &Path) -> PathBuf {
+    if let Ok(canonical) = path.canonicalize() {
+        return canonical;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => canonicalize_lenient(parent).join(name),
+        _ => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,205 @@
+use super::{front_matter, locale};
+use super::types::{AgentIndex, McpEndpoint};
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// `include` / `exclude` のパターンに一致するプロンプトファイルをエンドポイントとして登録する
+///
+/// IDとラベルは front matter の `id` / `title`、無ければファイル名から決める。
+/// `mcp_endpoints` に明示されたエントリ（同じIDまたは同じファイル）が優先される
+pub fn discover_endpoints(agent_lib_path: &Path, index: &AgentIndex) -> Result<Vec<McpEndpoint>> {
+    if index.include.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let include = build_glob_set(&index.include)?;
+    let exclude = build_glob_set(&index.exclude)?;
+
+    let mut taken_ids: HashSet<String> = index.mcp_endpoints.iter().map(|e| e.id.clone()).collect();
+    let explicit_files: HashSet<String> = index
+        .mcp_endpoints
+        .iter()
+        .flat_map(|e| std::iter::once(&e.prompt_file).chain(e.locales.values()))
+        .map(|file| normalize(file))
+        .collect();
+
+    let mut files: Vec<String> = WalkDir::new(agent_lib_path)
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(agent_lib_path).ok()?;
+            Some(normalize(&relative.to_string_lossy()))
+        })
+        .filter(|relative| {
+            Path::new(relative).extension().and_then(|e| e.to_str()) == Some("md")
+                && include.is_match(relative)
+                && !exclude.is_match(relative)
+                && !explicit_files.contains(relative)
+                && !is_locale_variant(agent_lib_path, relative)
+        })
+        .collect();
+    files.sort();
+
+    let mut endpoints = Vec::new();
+    for relative in files {
+        let path = agent_lib_path.join(&relative);
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let (front_matter, _) = front_matter::split_front_matter(&source)
+            .with_context(|| format!("Invalid front matter in {}", path.display()))?;
+        let front_matter = front_matter.unwrap_or_default();
+
+        let stem = Path::new(&relative)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let id = if let Some(id) = front_matter.id.filter(|id| !id.is_empty()) {
+            // front matter で明示されたIDは明示エントリと重複する場合のみスキップ
+            if taken_ids.contains(&id) {
+                continue;
+            }
+            id
+        } else if taken_ids.contains(&stem) {
+            // 同名のファイルがある場合はパスからIDを作る
+            relative.trim_end_matches(".md").replace('/', "_")
+        } else {
+            stem.clone()
+        };
+        if !taken_ids.insert(id.clone()) {
+            continue;
+        }
+
+        endpoints.push(McpEndpoint {
+            id,
+            label: front_matter.title.unwrap_or(stem),
+            description: front_matter.description.unwrap_or_default(),
+            prompt_file: relative,
+            trigger: None,
+            category: None,
+            tags: Vec::new(),
+            arguments: Vec::new(),
+            locales: BTreeMap::new(),
+            attachments: Vec::new(),
+            aliases: Vec::new(),
+            deprecated: None,
+            discovered: true,
+        });
+    }
+
+    Ok(endpoints)
+}
+
+// `review.ja.md` のように基本ファイルが存在するロケール別ファイルは個別に登録しない
+fn is_locale_variant(agent_lib_path: &Path, relative: &str) -> bool {
+    let path = Path::new(relative);
+    path.file_name()
+        .and_then(|name| locale::split_variant(&name.to_string_lossy()))
+        .is_some_and(|(base, _)| agent_lib_path.join(path.with_file_name(base)).is_file())
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        // `*` はディレクトリ区切りをまたがず、`**` でサブディレクトリに一致させる
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("Invalid glob pattern '{pattern}'"))?;
+        builder.add(glob);
+    }
+    builder.build().context("Failed to build glob patterns")
+}
+
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches("./").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, name: &str, content: &str) {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn explicit(id: &str, prompt_file: &str) -> McpEndpoint {
+        McpEndpoint {
+            id: id.to_string(),
+            label: id.to_string(),
+            description: String::new(),
+            prompt_file: prompt_file.to_string(),
+            trigger: None,
+            category: None,
+            tags: Vec::new(),
+            arguments: Vec::new(),
+            locales: BTreeMap::new(),
+            attachments: Vec::new(),
+            aliases: Vec::new(),
+            deprecated: None,
+            discovered: false,
+        }
+    }
+
+    #[test]
+    fn test_discover_endpoints() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(root, "prompts/review.md", "---\ntitle: Code Review\n---\n# Review");
+        write(root, "prompts/commit.md", "---\nid: commit_message\n---\n# Commit");
+        write(root, "prompts/drafts/wip.md", "# WIP");
+        write(root, "prompts/explicit.md", "# Explicit");
+        write(root, "prompts/notes.txt", "not a prompt");
+        write(root, "prompts/review.ja.md", "# レビュー");
+
+        let index = AgentIndex {
+            include: vec!["prompts/**/*.md".to_string()],
+            exclude: vec!["prompts/drafts/**".to_string()],
+            mcp_endpoints: vec![explicit("explicit", "prompts/explicit.md")],
+            ..Default::default()
+        };
+
+        let endpoints = discover_endpoints(root, &index).unwrap();
+        let ids: Vec<_> = endpoints.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["commit_message", "review"]);
+        assert_eq!(endpoints[1].label, "Code Review");
+        assert_eq!(endpoints[1].prompt_file, "prompts/review.md");
+        assert!(endpoints.iter().all(|e| e.discovered));
+    }
+
+    #[test]
+    fn test_explicit_entries_override_discovered_ids() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(root, "prompts/review.md", "# Review");
+        write(root, "other/review.md", "# Other review");
+
+        let index = AgentIndex {
+            include: vec!["**/*.md".to_string()],
+            mcp_endpoints: vec![explicit("review", "custom.md")],
+            ..Default::default()
+        };
+
+        let endpoints = discover_endpoints(root, &index).unwrap();
+        let ids: Vec<_> = endpoints.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["other_review", "prompts_review"]);
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let index = AgentIndex {
+            include: vec!["prompts/[".to_string()],
+            ..Default::default()
+        };
+        let error = discover_endpoints(Path::new("."), &index).unwrap_err();
+        assert!(error.to_string().contains("Invalid glob pattern"));
+    }
+}
@@ -0,0 +1,440 @@
+use super::history::{self, SnapshotKind};
+use super::index_file::{self, IndexDocument};
+use super::locale;
+use super::parser::AgentLibraryParser;
+use super::storage;
+use super::types::{AgentIndex, McpEndpoint};
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// エンドポイントを追加し、プロンプトファイルを作成する
+///
+/// `content` を省略した場合は見出しだけのプロンプトを作る。既存のファイルを指定した場合は `content` を指定できない
+pub fn create_endpoint(repo_path: &Path, endpoint: &McpEndpoint, content: Option<&str>) -> Result<()> {
+    let agent_lib_path = library_path(repo_path)?;
+    let mut document = IndexDocument::load(&agent_lib_path.join("agent_index.yml"))?;
+    let index = document.index()?;
+
+    validate_id(&endpoint.id)?;
+    ensure_available(&index, &endpoint.id, None)?;
+    validate_prompt_file(&endpoint.prompt_file)?;
+    let prompt_path = agent_lib_path.join(&endpoint.prompt_file);
+    if prompt_path.exists() && content.is_some() {
+        return Err(anyhow::anyhow!("{} already exists", endpoint.prompt_file));
+    }
+
+    let endpoint = McpEndpoint {
+        discovered: false,
+        ..endpoint.clone()
+    };
+    document.insert_endpoint(&index_file::endpoint_value(&endpoint), None)?;
+    check_document(&document)?;
+
+    if !prompt_path.exists() {
+        let title = if endpoint.label.is_empty() { &endpoint.id } else { &endpoint.label };
+        let content = content.map_or_else(|| format!("# {title}\n"), ToString::to_string);
+        write_file(&prompt_path, &content)?;
+    }
+    document.save()?;
+    AgentLibraryParser::invalidate(&agent_lib_path);
+    Ok(())
+}
+
+/// エンドポイントのIDを変更する
+///
+/// `keep_alias` の場合は以前のIDを別名に残す。プロンプトファイル名がIDと同じ場合はファイル名も変更する
+pub fn rename_endpoint(repo_path: &Path, id: &str, new_id: &str, keep_alias: bool) -> Result<()> {
+    let agent_lib_path = library_path(repo_path)?;
+    let mut document = IndexDocument::load(&agent_lib_path.join("agent_index.yml"))?;
+    let index = document.index()?;
+    let endpoint = find(&index, id)?;
+
+    validate_id(new_id)?;
+    ensure_available(&index, new_id, Some(id))?;
+
+    let original = document.clone();
+    document.set_value(id, "id", new_id)?;
+    if keep_alias && !endpoint.aliases.iter().any(|a| a == id) {
+        document.add_alias(new_id, id)?;
+    }
+
+    // ファイル名がIDと同じ場合はファイル名（自動検出するロケール別ファイルを含む）も合わせる
+    let renamed = renamed_prompt_file(&endpoint.prompt_file, id, new_id)
+        .filter(|_| !is_shared(&index, &endpoint.prompt_file, id))
+        .map(|file| {
+            let moves: Vec<(PathBuf, PathBuf)> = std::iter::once((agent_lib_path.join(&endpoint.prompt_file), agent_lib_path.join(&file)))
+                .chain(
+                    detected_variants(&agent_lib_path, endpoint)
+                        .into_iter()
+                        .map(|(locale, path)| (path, agent_lib_path.join(variant_file(&file, &locale)))),
+                )
+                .filter(|(from, _)| from.is_file())
+                .collect();
+            (file, moves)
+        })
+        .filter(|(file, moves)| !agent_lib_path.join(file).exists() && moves.iter().all(|(_, to)| !to.exists()));
+    if let Some((file, _)) = &renamed {
+        document.set_value(new_id, "prompt_file", file)?;
+    }
+    check_document(&document)?;
+
+    // インデックスを先に保存し、ファイル名を変更できない場合は元に戻す
+    document.save()?;
+    if let Some((_, moves)) = &renamed {
+        if let Err(e) = rename_files(moves) {
+            original.save()?;
+            return Err(e);
+        }
+    }
+    AgentLibraryParser::invalidate(&agent_lib_path);
+    Ok(())
+}
+
+/// エンドポイントを複製する（プロンプトファイルとロケール別ファイルもコピーし、別名と非推奨の設定は引き継がない）
+pub fn duplicate_endpoint(repo_path: &Path, id: &str, new_id: &str) -> Result<McpEndpoint> {
+    let agent_lib_path = library_path(repo_path)?;
+    let mut document = IndexDocument::load(&agent_lib_path.join("agent_index.yml"))?;
+    let index = document.index()?;
+    let endpoint = find(&index, id)?;
+
+    validate_id(new_id)?;
+    ensure_available(&index, new_id, None)?;
+
+    let prompt_file = renamed_prompt_file(&endpoint.prompt_file, id, new_id).unwrap_or_else(|| {
+        let parent = Path::new(&endpoint.prompt_file).parent().map(|p| p.to_string_lossy().to_string());
+        match parent.filter(|p| !p.is_empty()) {
+            Some(parent) => format!("{parent}/{new_id}.md"),
+            None => format!("{new_id}.md"),
+        }
+    });
+
+    // ロケール別ファイルは `<ファイル名>.<ロケール>.md` にコピーする（`locales` で指定したものは `locales` に残す）
+    let mut copies = vec![(agent_lib_path.join(&endpoint.prompt_file), prompt_file.clone())];
+    let mut locales = BTreeMap::new();
+    for (locale, path) in locale::variant_files(&agent_lib_path, endpoint) {
+        if !path.is_file() {
+            continue;
+        }
+        let file = variant_file(&prompt_file, &locale);
+        if endpoint.locales.contains_key(&locale) {
+            locales.insert(locale, file.clone());
+        }
+        copies.push((path, file));
+    }
+    if let Some((_, file)) = copies.iter().find(|(_, file)| agent_lib_path.join(file).exists()) {
+        return Err(anyhow::anyhow!("{file} already exists"));
+    }
+
+    let duplicate = McpEndpoint {
+        id: new_id.to_string(),
+        prompt_file,
+        locales,
+        aliases: Vec::new(),
+        deprecated: None,
+        ..endpoint.clone()
+    };
+    document.insert_endpoint(&index_file::endpoint_value(&duplicate), Some(id))?;
+    check_document(&document)?;
+
+    let mut written = Vec::new();
+    let result = copies.iter().try_for_each(|(source, file)| {
+        let content = fs::read_to_string(source).with_context(|| format!("Failed to read {}", source.display()))?;
+        let target = agent_lib_path.join(file);
+        write_file(&target, &content)?;
+        written.push(target);
+        Ok(())
+    });
+    if let Err(e) = result.and_then(|()| document.save()) {
+        for path in &written {
+            let _ = fs::remove_file(path);
+        }
+        return Err(e);
+    }
+    AgentLibraryParser::invalidate(&agent_lib_path);
+    Ok(duplicate)
+}
+
+/// エンドポイントを `order` の順に並べ替える（`agent_index.yml` のすべてのIDを指定する）
+pub fn reorder_endpoints(repo_path: &Path, order: &[String]) -> Result<()> {
+    let agent_lib_path = library_path(repo_path)?;
+    let mut document = IndexDocument::load(&agent_lib_path.join("agent_index.yml"))?;
+
+    document.reorder_endpoints(order)?;
+    check_document(&document)?;
+    document.save()?;
+    AgentLibraryParser::invalidate(&agent_lib_path);
+    Ok(())
+}
+
+/// エンドポイントを削除する
+///
+/// `delete_files` の場合はプロンプトファイルとロケール別ファイル（自動検出したものを含む）も削除する。
+/// 他のエンドポイントが使うファイルは残す
+pub fn delete_endpoint(repo_path: &Path, id: &str, delete_files: bool) -> Result<()> {
+    let agent_lib_path = library_path(repo_path)?;
+    let mut document = IndexDocument::load(&agent_lib_path.join("agent_index.yml"))?;
+    let index = document.index()?;
+    let endpoint = find(&index, id)?;
+    let shared_prompt = is_shared(&index, &endpoint.prompt_file, id);
+    let mut files = vec![(endpoint.prompt_file.clone(), shared_prompt)];
+    for (locale, path) in locale::variant_files(&agent_lib_path, endpoint) {
+        if let Some(file) = relative_path(&agent_lib_path, &path) {
+            // 自動検出したファイルはプロンプトファイルを共有している場合に残す
+            let shared = is_shared(&index, &file, id) || (!endpoint.locales.contains_key(&locale) && shared_prompt);
+            files.push((file, shared));
+        }
+    }
+
+    document.remove_endpoint(id)?;
+    check_document(&document)?;
+    document.save()?;
+
+    if delete_files {
+        for (file, shared) in &files {
+            let path = agent_lib_path.join(file);
+            if validate_prompt_file(file).is_ok() && !shared && path.is_file() {
+                let previous = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+                history::record(&path, &previous, SnapshotKind::Delete)?;
+                fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+        }
+    }
+    AgentLibraryParser::invalidate(&agent_lib_path);
+    Ok(())
+}
+
+fn library_path(repo_path: &Path) -> Result<PathBuf> {
+    let agent_lib_path = repo_path.join(".agent_library");
+    if !agent_lib_path.join("agent_index.yml").is_file() {
+        return Err(anyhow::anyhow!("agent_index.yml not found in {}", agent_lib_path.display()));
+    }
+    Ok(agent_lib_path)
+}
+
+fn find<'a>(index: &'a AgentIndex, id: &str) -> Result<&'a McpEndpoint> {
+    index
+        .mcp_endpoints
+        .iter()
+        .find(|e| e.id == id)
+        .with_context(|| format!("Endpoint '{id}' is not defined in agent_index.yml"))
+}
+
+fn validate_id(id: &str) -> Result<()> {
+    if id.is_empty() || !id.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.') {
+        return Err(anyhow::anyhow!(
+            "Invalid endpoint id '{id}': use letters, digits, '_', '-' or '.'"
+        ));
+    }
+    Ok(())
+}
+
+// IDが他のエンドポイントのIDや別名と重複しないことを確認する（`except` のエンドポイントは除く）
+fn ensure_available(index: &AgentIndex, id: &str, except: Option<&str>) -> Result<()> {
+    let taken = index
+        .mcp_endpoints
+        .iter()
+        .filter(|e| Some(e.id.as_str()) != except)
+        .any(|e| e.id == id || e.aliases.iter().any(|a| a == id));
+    if taken {
+        return Err(anyhow::anyhow!("Endpoint id '{id}' is already used"));
+    }
+    Ok(())
+}
+
+// `.agent_library` 内の Markdown ファイルであることを確認する
+fn validate_prompt_file(prompt_file: &str) -> Result<()> {
+    let path = Path::new(prompt_file);
+    let inside = path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if prompt_file.is_empty() || !inside {
+        return Err(anyhow::anyhow!("Prompt file '{prompt_file}' must be a relative path inside .agent_library"));
+    }
+    if path.extension().and_then(|e| e.to_str()) != Some("md") {
+        return Err(anyhow::anyhow!("Prompt file '{prompt_file}' must be a .md file"));
+    }
+    Ok(())
+}
+
+// 編集後の内容を書き込む前に検証する
+fn check_document(document: &IndexDocument) -> Result<()> {
+    let index = document.index()?;
+    let mut names = HashSet::new();
+    for endpoint in &index.mcp_endpoints {
+        for name in std::iter::once(&endpoint.id).chain(&endpoint.aliases) {
+            if !names.insert(name.as_str()) {
+                return Err(anyhow::anyhow!("Endpoint id or alias '{name}' is used more than once"));
+            }
+        }
+    }
+    Ok(())
+}
+
+// ファイル名（拡張子を除く）がIDと同じ場合に新しいIDのファイル名を返す
+fn renamed_prompt_file(prompt_file: &str, id: &str, new_id: &str) -> Option<String> {
+    let (directory, name) = prompt_file.rsplit_once('/').map_or(("", prompt_file), |(d, n)| (d, n));
+    if name.strip_suffix(".md") != Some(id) {
+        return None;
+    }
+    Some(if directory.is_empty() { format!("{new_id}.md") } else { format!("{directory}/{new_id}.md") })
+}
+
+// 他のエンドポイントも使っているファイル
+fn is_shared(index: &AgentIndex, file: &str, id: &str) -> bool {
+    index
+        .mcp_endpoints
+        .iter()
+        .filter(|e| e.id != id)
+        .any(|e| e.prompt_file == file || e.locales.values().any(|f| f == file))
+}
+
+// `locales` で指定せずに自動検出したロケール別ファイル
+fn detected_variants(agent_lib_path: &Path, endpoint: &McpEndpoint) -> Vec<(String, PathBuf)> {
+    locale::variant_files(agent_lib_path, endpoint)
+        .into_iter()
+        .filter(|(locale, _)| !endpoint.locales.contains_key(locale))
+        .collect()
+}
+
+// プロンプトファイルに対応するロケール別ファイル名（`prompts/review.md` → `prompts/review.ja.md`）
+fn variant_file(prompt_file: &str, locale: &str) -> String {
+    format!("{}.{locale}.md", prompt_file.strip_suffix(".md").unwrap_or(prompt_file))
+}
+
+fn relative_path(agent_lib_path: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(agent_lib_path).ok()?;
+    Some(relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+}
+
+// ファイル名を変更する（途中で失敗した場合は変更したファイルを元に戻す）
+fn rename_files(moves: &[(PathBuf, PathBuf)]) -> Result<()> {
+    for (n, (from, to)) in moves.iter().enumerate() {
+        if let Err(e) = fs::rename(from, to) {
+            for (from, to) in moves[..n].iter().rev() {
+                let _ = fs::rename(to, from);
+            }
+            return Err(e).with_context(|| format!("Failed to rename {}", from.display()));
+        }
+    }
+    Ok(())
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    storage::write_atomic(path, content.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const INDEX: &str = "# Team prompts
+mcp_endpoints:
+  # Code review
+  - id: review # keep short
+    label: Review
+    prompt_file: prompts/review.md
+
+  - id: commit
+    prompt_file: commit.md
+";
+
+    fn setup() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let agent_lib_dir = temp_dir.path().join(".agent_library");
+        fs::create_dir_all(agent_lib_dir.join("prompts")).unwrap();
+        fs::write(agent_lib_dir.join("agent_index.yml"), INDEX).unwrap();
+        fs::write(agent_lib_dir.join("prompts/review.md"), "# Review").unwrap();
+        fs::write(agent_lib_dir.join("prompts/review.ja.md"), "# レビュー").unwrap();
+        fs::write(agent_lib_dir.join("commit.md"), "# Commit").unwrap();
+        temp_dir
+    }
+
+    fn ids(repo: &Path) -> Vec<String> {
+        AgentLibraryParser::parse(repo).unwrap().prompts.into_iter().map(|p| p.id).collect()
+    }
+
+    #[test]
+    fn test_create_duplicate_and_reorder() {
+        let temp_dir = setup();
+        let repo = temp_dir.path();
+
+        let endpoint = McpEndpoint {
+            id: "tests".to_string(),
+            label: "Tests".to_string(),
+            prompt_file: "prompts/tests.md".to_string(),
+            ..Default::default()
+        };
+        create_endpoint(repo, &endpoint, None).unwrap();
+        assert_eq!(fs::read_to_string(repo.join(".agent_library/prompts/tests.md")).unwrap(), "# Tests\n");
+        assert!(create_endpoint(repo, &endpoint, None).unwrap_err().to_string().contains("already used"));
+
+        let duplicate = duplicate_endpoint(repo, "review", "review_strict").unwrap();
+        assert_eq!(duplicate.prompt_file, "prompts/review_strict.md");
+        let library = AgentLibraryParser::parse(repo).unwrap();
+        assert_eq!(library.find_prompt("review_strict").unwrap().variants.len(), 1);
+        assert_eq!(
+            fs::read_to_string(repo.join(".agent_library/prompts/review_strict.ja.md")).unwrap(),
+            "# レビュー"
+        );
+        assert_eq!(ids(repo), vec!["review", "review_strict", "commit", "tests"]);
+
+        reorder_endpoints(repo, &["tests".to_string(), "commit".to_string(), "review".to_string(), "review_strict".to_string()]).unwrap();
+        assert_eq!(ids(repo), vec!["tests", "commit", "review", "review_strict"]);
+        assert!(reorder_endpoints(repo, &["tests".to_string()]).is_err());
+
+        let index = fs::read_to_string(repo.join(".agent_library/agent_index.yml")).unwrap();
+        assert!(index.starts_with("# Team prompts\n"));
+        assert!(index.contains("  # Code review\n  - id: review # keep short\n"));
+    }
+
+    #[test]
+    fn test_rename_and_delete() {
+        let temp_dir = setup();
+        let repo = temp_dir.path();
+        let agent_lib_dir = repo.join(".agent_library");
+
+        assert!(rename_endpoint(repo, "review", "commit", true).is_err());
+        assert!(rename_endpoint(repo, "review", "bad id", true).is_err());
+
+        rename_endpoint(repo, "review", "code_review", true).unwrap();
+        let library = AgentLibraryParser::parse(repo).unwrap();
+        assert_eq!(library.find_prompt("review").unwrap().id, "code_review");
+        assert!(agent_lib_dir.join("prompts/code_review.md").is_file());
+        assert!(!agent_lib_dir.join("prompts/review.md").exists());
+        // 自動検出するロケール別ファイルも合わせて変更する
+        assert!(agent_lib_dir.join("prompts/code_review.ja.md").is_file());
+        assert!(!agent_lib_dir.join("prompts/review.ja.md").exists());
+        assert_eq!(library.find_prompt("code_review").unwrap().variants.len(), 1);
+        let index = fs::read_to_string(agent_lib_dir.join("agent_index.yml")).unwrap();
+        assert!(index.contains("  - id: code_review # keep short\n    aliases: [review]\n"), "{index}");
+
+        delete_endpoint(repo, "code_review", true).unwrap();
+        assert_eq!(ids(repo), vec!["commit"]);
+        assert!(!agent_lib_dir.join("prompts/code_review.md").exists());
+        assert!(!agent_lib_dir.join("prompts/code_review.ja.md").exists());
+        assert!(delete_endpoint(repo, "missing", false).is_err());
+    }
+
+    #[test]
+    fn test_explicit_locales_follow_duplicate_and_delete() {
+        let temp_dir = setup();
+        let repo = temp_dir.path();
+        let agent_lib_dir = repo.join(".agent_library");
+        let index = INDEX.replace("    prompt_file: commit.md\n", "    prompt_file: commit.md\n    locales:\n      fr: translations/commit-fr.md\n");
+        fs::write(agent_lib_dir.join("agent_index.yml"), index).unwrap();
+        fs::create_dir_all(agent_lib_dir.join("translations")).unwrap();
+        fs::write(agent_lib_dir.join("translations/commit-fr.md"), "# Commit (fr)").unwrap();
+
+        // `locales` で指定したファイルは複製先の `locales` に残す
+        let duplicate = duplicate_endpoint(repo, "commit", "commit_short").unwrap();
+        assert_eq!(duplicate.locales.get("fr").map(String::as_str), Some("commit_short.fr.md"));
+        assert_eq!(fs::read_to_string(agent_lib_dir.join("commit_short.fr.md")).unwrap(), "# Commit (fr)");
+        assert!(duplicate_endpoint(repo, "commit", "commit_short").is_err());
+
+        delete_endpoint(repo, "commit", true).unwrap();
+        assert!(!agent_lib_dir.join("commit.md").exists());
+        assert!(!agent_lib_dir.join("translations/commit-fr.md").exists());
+        assert!(agent_lib_dir.join("commit_short.fr.md").is_file());
+    }
+}
@@ -0,0 +1,360 @@
+use super::importer::ToolFormat;
+use super::types::{AgentLibrary, Prompt};
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// 生成したファイルの目印。この行があるファイルだけを上書き・削除する
+pub const GENERATED_MARKER: &str = "<!-- Generated by Librarian";
+
+// `{{name}}`（ブロックやインクルードは含まない）
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{\{\s*([\w.-]+)\s*\}\}").unwrap());
+static BLOCK_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{\{\s*(?:[#/]|else\s*\}\})").unwrap());
+
+/// 書き出したファイルの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportStatus {
+    Created,
+    Updated,
+    Unchanged,
+    // ライブラリから無くなったプロンプトの生成ファイル
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedFile {
+    pub id: String,
+    // リポジトリからの相対パス
+    pub path: String,
+    pub status: ExportStatus,
+}
+
+/// 変換できなかった内容
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportIssue {
+    pub id: String,
+    pub path: String,
+    pub message: String,
+}
+
+/// 書き出しの結果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportSummary {
+    pub files: Vec<ExportedFile>,
+    // 書き出したが一部を変換できなかったもの
+    pub warnings: Vec<ExportIssue>,
+    // 書き出さなかったファイル（手書きのファイルなど）
+    pub skipped: Vec<ExportIssue>,
+}
+
+/// 生成したファイルかどうか
+#[must_use] pub fn is_generated(content: &str) -> bool {
+    content.lines().any(|line| line.trim_start().starts_with(GENERATED_MARKER))
+}
+
+/// ライブラリのプロンプトを他ツールの形式のファイルとしてリポジトリに書き出す
+///
+/// `formats` が空の場合はすべての形式に書き出す。内容が同じファイルは書き換えず、
+/// 目印の無い手書きのファイルは上書きしない。ライブラリから無くなったプロンプトの生成ファイルは削除する。
+/// `dry_run` の場合は結果だけを返し、ファイルは書き込まない
+pub fn export_prompts(
+    library: &AgentLibrary,
+    repo_path: &Path,
+    formats: &[ToolFormat],
+    dry_run: bool,
+) -> Result<ExportSummary> {
+    let formats = if formats.is_empty() { &ToolFormat::ALL[..] } else { formats };
+    let mut summary = ExportSummary::default();
+
+    for &format in formats {
+        let (directory, extension) = target(format);
+        let mut written = BTreeSet::new();
+
+        for prompt in &library.prompts {
+            let relative = format!("{directory}/{}{extension}", file_stem(&prompt.id));
+            let issue = |message: String| ExportIssue {
+                id: prompt.id.clone(),
+                path: relative.clone(),
+                message,
+            };
+            let (content, warnings) = render(format, prompt);
+            written.insert(relative.clone());
+
+            let path = repo_path.join(&relative);
+            let status = match fs::read_to_string(&path) {
+                Ok(existing) if !is_generated(&existing) => {
+                    summary.skipped.push(issue("File was not generated by Librarian and was not overwritten".to_string()));
+                    continue;
+                }
+                Ok(existing) if existing == content => ExportStatus::Unchanged,
+                Ok(_) => ExportStatus::Updated,
+                Err(_) if path.exists() => {
+                    summary.skipped.push(issue("Existing file could not be read and was not overwritten".to_string()));
+                    continue;
+                }
+                Err(_) => ExportStatus::Created,
+            };
+
+            if !dry_run && status != ExportStatus::Unchanged {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+                }
+                fs::write(&path, &content).with_context(|| format!("Failed to write {}", path.display()))?;
+            }
+            summary.warnings.extend(warnings.into_iter().map(issue));
+            summary.files.push(ExportedFile {
+                id: prompt.id.clone(),
+                path: relative,
+                status,
+            });
+        }
+
+        for (relative, id) in stale_files(repo_path, directory, extension, &written) {
+            if !dry_run {
+                let path = repo_path.join(&relative);
+                fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+            summary.files.push(ExportedFile {
+                id,
+                path: relative,
+                status: ExportStatus::Removed,
+            });
+        }
+    }
+
+    if !dry_run {
+        tracing::info!("Exported {} prompt files to {}", summary.files.len(), repo_path.display());
+    }
+    Ok(summary)
+}
+
+// 形式ごとの書き出し先（リポジトリからの相対パス）と拡張子
+const fn target(format: ToolFormat) -> (&'static str, &'static str) {
+    match format {
+        ToolFormat::ClaudeCommands => (".claude/commands", ".md"),
+        ToolFormat::CursorRules => (".cursor/rules", ".mdc"),
+        ToolFormat::GithubPrompts => (".github/prompts", ".prompt.md"),
+    }
+}
+
+// 以前に生成したが今回は書き出さなかったファイル（相対パス, ID）
+fn stale_files(repo_path: &Path, directory: &str, extension: &str, written: &BTreeSet<String>) -> Vec<(String, String)> {
+    let Ok(entries) = fs::read_dir(repo_path.join(directory)) else {
+        return Vec::new();
+    };
+
+    let mut stale: Vec<(String, String)> = entries
+        .filter_map(std::result::Result::ok)
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let id = name.strip_suffix(extension)?.to_string();
+            let relative = format!("{directory}/{name}");
+            let generated = fs::read_to_string(entry.path()).is_ok_and(|content| is_generated(&content));
+            (generated && !written.contains(&relative)).then_some((relative, id))
+        })
+        .collect();
+    stale.sort();
+    stale
+}
+
+// プロンプトを形式に合わせたファイルの内容にする
+fn render(format: ToolFormat, prompt: &Prompt) -> (String, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut front_matter = Mapping::new();
+    if !prompt.description.is_empty() {
+        front_matter.insert("description".into(), prompt.description.clone().into());
+    }
+
+    let body = match format {
+        ToolFormat::ClaudeCommands => {
+            let (body, hint) = claude_placeholders(prompt, &mut warnings);
+            if let Some(hint) = hint {
+                front_matter.insert("argument-hint".into(), hint.into());
+            }
+            body
+        }
+        ToolFormat::CursorRules => {
+            front_matter.insert("alwaysApply".into(), false.into());
+            if !prompt.arguments.is_empty() {
+                warnings.push("Cursor rules do not take arguments; placeholders were kept as text".to_string());
+            }
+            prompt.content.clone()
+        }
+        ToolFormat::GithubPrompts => github_placeholders(prompt),
+    };
+
+    if format != ToolFormat::CursorRules && prompt.arguments.iter().any(|a| a.default.is_some()) {
+        warnings.push("Default argument values are not supported and were dropped".to_string());
+    }
+    if BLOCK_TAG.is_match(&body) {
+        warnings.push("Template blocks ({{#if}} / {{#each}}) are not supported and were kept as text".to_string());
+    }
+    if prompt.messages.len() > 1 {
+        warnings.push("Prompt has multiple messages; they were written as a single prompt".to_string());
+    }
+
+    let marker = format!(
+        "{GENERATED_MARKER} from agent library prompt '{}'. Edit the library instead; changes here will be overwritten. -->",
+        prompt.id
+    );
+    let front_matter = if front_matter.is_empty() {
+        String::new()
+    } else {
+        let yaml = serde_yaml::to_string(&Value::Mapping(front_matter)).unwrap_or_default();
+        format!("---\n{yaml}---\n")
+    };
+    (format!("{front_matter}{marker}\n\n{}\n", body.trim_end()), warnings)
+}
+
+// 引数が1つなら `$ARGUMENTS`、複数なら `$1` 以降にする（`arguments` という名前の引数は `$ARGUMENTS`）
+fn claude_placeholders(prompt: &Prompt, warnings: &mut Vec<String>) -> (String, Option<String>) {
+    let single = prompt.arguments.len() == 1;
+    let positional: Vec<&str> = prompt
+        .arguments
+        .iter()
+        .map(|a| a.name.as_str())
+        .filter(|name| !single && *name != "arguments")
+        .collect();
+
+    let mut unsupported = false;
+    let body = PLACEHOLDER.replace_all(&prompt.content, |caps: &Captures| {
+        let name = &caps[1];
+        if !prompt.arguments.iter().any(|a| a.name == name) {
+            return caps[0].to_string();
+        }
+        match positional.iter().position(|p| *p == name) {
+            None => "$ARGUMENTS".to_string(),
+            Some(index) if index < 9 => format!("${}", index + 1),
+            Some(_) => {
+                unsupported = true;
+                caps[0].to_string()
+            }
+        }
+    });
+    if unsupported {
+        warnings.push("Claude Code supports up to 9 positional arguments; the rest were kept as text".to_string());
+    }
+
+    let hint = if single {
+        Some(format!("[{}]", prompt.arguments[0].name))
+    } else {
+        Some(positional.iter().map(|name| format!("[{name}]")).collect::<Vec<_>>().join(" ")).filter(|h| !h.is_empty())
+    };
+    (body.into_owned(), hint)
+}
+
+// `{{name}}` を `${input:name:説明}` にする
+fn github_placeholders(prompt: &Prompt) -> String {
+    PLACEHOLDER
+        .replace_all(&prompt.content, |caps: &Captures| {
+            let Some(argument) = prompt.arguments.iter().find(|a| a.name == caps[1]) else {
+                return caps[0].to_string();
+            };
+            match argument.description.as_deref().map(|d| d.replace([':', '}'], " ")) {
+                Some(description) if !description.trim().is_empty() => {
+                    format!("${{input:{}:{}}}", argument.name, description.trim())
+                }
+                _ => format!("${{input:{}}}", argument.name),
+            }
+        })
+        .into_owned()
+}
+
+fn file_stem(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_library::types::PromptArgument;
+    use tempfile::TempDir;
+
+    fn argument(name: &str, description: Option<&str>) -> PromptArgument {
+        PromptArgument {
+            name: name.to_string(),
+            description: description.map(ToString::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn library() -> AgentLibrary {
+        AgentLibrary {
+            index: crate::agent_library::AgentIndex::default(),
+            base_path: std::path::PathBuf::new(),
+            prompts: vec![
+                Prompt {
+                    id: "review".to_string(),
+                    description: "Review a file: thoroughly".to_string(),
+                    content: "Review {{file}} focusing on {{ focus }}.\n".to_string(),
+                    arguments: vec![argument("file", Some("Path to review")), argument("focus", None)],
+                    ..Default::default()
+                },
+                Prompt {
+                    id: "commit".to_string(),
+                    description: "Write a commit message".to_string(),
+                    content: "Summarize {{changes}}".to_string(),
+                    arguments: vec![argument("changes", None)],
+                    ..Default::default()
+                },
+            ],
+            resources: Vec::new(),
+            taxonomy: crate::agent_library::Taxonomy::default(),
+        }
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let library = library();
+        let review = &library.prompts[0];
+
+        let (claude, warnings) = render(ToolFormat::ClaudeCommands, review);
+        assert!(claude.starts_with("---\ndescription: 'Review a file: thoroughly'\nargument-hint: '[file] [focus]'\n---\n<!-- Generated by Librarian"));
+        assert!(claude.ends_with("\n\nReview $1 focusing on $2.\n"));
+        assert!(warnings.is_empty());
+
+        let (claude, _) = render(ToolFormat::ClaudeCommands, &library.prompts[1]);
+        assert!(claude.ends_with("Summarize $ARGUMENTS\n"));
+
+        let (github, _) = render(ToolFormat::GithubPrompts, review);
+        assert!(github.ends_with("Review ${input:file:Path to review} focusing on ${input:focus}.\n"));
+
+        let (cursor, warnings) = render(ToolFormat::CursorRules, review);
+        assert!(cursor.contains("alwaysApply: false"));
+        assert_eq!(warnings.len(), 1);
+        assert!(is_generated(&cursor));
+    }
+
+    #[test]
+    fn test_export_is_idempotent_and_keeps_hand_written_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path();
+        fs::create_dir_all(repo.join(".claude/commands")).unwrap();
+        fs::write(repo.join(".claude/commands/commit.md"), "My own commit command").unwrap();
+
+        let mut library = library();
+        let summary = export_prompts(&library, repo, &[ToolFormat::ClaudeCommands], false).unwrap();
+        assert_eq!(summary.files.len(), 1);
+        assert_eq!(summary.files[0].status, ExportStatus::Created);
+        assert_eq!(summary.skipped[0].path, ".claude/commands/commit.md");
+        assert_eq!(fs::read_to_string(repo.join(".claude/commands/commit.md")).unwrap(), "My own commit command");
+
+        let summary = export_prompts(&library, repo, &[ToolFormat::ClaudeCommands], false).unwrap();
+        assert_eq!(summary.files[0].status, ExportStatus::Unchanged);
+
+        // ライブラリから消えたプロンプトの生成ファイルは削除する
+        library.prompts.remove(0);
+        let summary = export_prompts(&library, repo, &[ToolFormat::ClaudeCommands], false).unwrap();
+        assert_eq!(summary.files[0].status, ExportStatus::Removed);
+        assert!(!repo.join(".claude/commands/review.md").exists());
+    }
+}
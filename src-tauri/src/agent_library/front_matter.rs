@@ -0,0 +1,212 @@
+use super::types::{McpEndpoint, PromptArgument};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// プロンプトファイル先頭の `---` で囲まれたメタデータ
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct FrontMatter {
+    // 自動登録時のエンドポイントID
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub trigger: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub arguments: Vec<PromptArgument>,
+}
+
+/// front matter とインデックスのエントリをマージした結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PromptMetadata {
+    pub title: String,
+    pub description: String,
+    pub category: Option<String>,
+    pub trigger: Option<String>,
+    pub tags: Vec<String>,
+    pub arguments: Vec<PromptArgument>,
+}
+
+/// front matter と本文を分割する
+///
+/// front matter がない場合（閉じ区切りがない場合を含む）は全体を本文として返す
+pub fn split_front_matter(source: &str) -> Result<(Option<FrontMatter>, &str)> {
+    let Some((yaml, body)) = split_raw(source) else {
+        return Ok((None, source));
+    };
+
+    let front_matter = if yaml.trim().is_empty() {
+        FrontMatter::default()
+    } else {
+        serde_yaml::from_str(yaml).context("Failed to parse front matter")?
+    };
+
+    Ok((Some(front_matter), body))
+}
+
+/// front matter の YAML 部分と本文を文字列のまま分割する
+#[must_use] pub fn split_raw(source: &str) -> Option<(&str, &str)> {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    let after_open = source
+        .strip_prefix("---\r\n")
+        .or_else(|| source.strip_prefix("---\n"))?;
+
+    let mut offset = 0;
+    for line in after_open.split_inclusive('\n') {
+        let trimmed = line.trim_end_matches(['\r', '\n']);
+        if trimmed == "---" || trimmed == "..." {
+            let yaml = &after_open[..offset];
+            let body = after_open[offset + line.len()..].trim_start_matches(['\r', '\n']);
+            return Some((yaml, body));
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+/// インデックスのエントリと front matter をマージする
+///
+/// 優先順位: front matter > `agent_index.yml`。
+/// 引数は名前単位でマージし、同名の引数は front matter の定義で置き換える。タグは両方を合わせる。
+/// タイトルがどちらにも無い場合はエンドポイントIDを使う
+#[must_use] pub fn merge(endpoint: &McpEndpoint, front_matter: Option<&FrontMatter>) -> PromptMetadata {
+    let fm = front_matter.cloned().unwrap_or_default();
+
+    let title = fm
+        .title
+        .filter(|t| !t.is_empty())
+        .or_else(|| Some(endpoint.label.clone()).filter(|l| !l.is_empty()))
+        .unwrap_or_else(|| endpoint.id.clone());
+    let description = fm
+        .description
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| endpoint.description.clone());
+
+    let mut arguments = endpoint.arguments.clone();
+    for argument in fm.arguments {
+        if let Some(existing) = arguments.iter_mut().find(|a| a.name == argument.name) {
+            *existing = argument;
+        } else {
+            arguments.push(argument);
+        }
+    }
+
+    // タグは両方の指定を合わせる
+    let mut tags = endpoint.tags.clone();
+    for tag in fm.tags {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    PromptMetadata {
+        title,
+        description,
+        category: fm.category.or_else(|| endpoint.category.clone()),
+        trigger: fm.trigger.or_else(|| endpoint.trigger.clone()),
+        tags,
+        arguments,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn endpoint() -> McpEndpoint {
+        McpEndpoint {
+            id: "review".to_string(),
+            label: "Review".to_string(),
+            description: "Index description".to_string(),
+            prompt_file: "review.md".to_string(),
+            trigger: Some("review".to_string()),
+            category: Some("development".to_string()),
+            tags: vec!["quality".to_string()],
+            arguments: vec![PromptArgument {
+                name: "file".to_string(),
+                required: true,
+                ..Default::default()
+            }],
+            locales: BTreeMap::new(),
+            attachments: Vec::new(),
+            aliases: Vec::new(),
+            deprecated: None,
+            discovered: false,
+        }
+    }
+
+    #[test]
+    fn test_split_front_matter() {
+        let source = "---\ntitle: Code Review\ntags: [rust, review]\n---\n\n# Body\n";
+        let (front_matter, body) = split_front_matter(source).unwrap();
+
+        let front_matter = front_matter.unwrap();
+        assert_eq!(front_matter.title.as_deref(), Some("Code Review"));
+        assert_eq!(front_matter.tags, vec!["rust".to_string(), "review".to_string()]);
+        assert_eq!(body, "# Body\n");
+    }
+
+    #[test]
+    fn test_split_without_front_matter() {
+        let source = "# Body\n\n---\n\nfooter";
+        let (front_matter, body) = split_front_matter(source).unwrap();
+        assert!(front_matter.is_none());
+        assert_eq!(body, source);
+
+        // 閉じ区切りがない場合は本文として扱う
+        let (front_matter, body) = split_front_matter("---\nnot closed").unwrap();
+        assert!(front_matter.is_none());
+        assert_eq!(body, "---\nnot closed");
+    }
+
+    #[test]
+    fn test_split_invalid_front_matter() {
+        let result = split_front_matter("---\ntitle: [unclosed\n---\nbody");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_merge_precedence() {
+        let front_matter = FrontMatter {
+            description: Some("Front matter description".to_string()),
+            category: Some("quality".to_string()),
+            tags: vec!["rust".to_string(), "quality".to_string()],
+            arguments: vec![
+                PromptArgument {
+                    name: "file".to_string(),
+                    required: false,
+                    default: Some("main.rs".to_string()),
+                    ..Default::default()
+                },
+                PromptArgument {
+                    name: "focus".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let metadata = merge(&endpoint(), Some(&front_matter));
+        assert_eq!(metadata.title, "Review");
+        assert_eq!(metadata.description, "Front matter description");
+        assert_eq!(metadata.category.as_deref(), Some("quality"));
+        assert_eq!(metadata.trigger.as_deref(), Some("review"));
+        assert_eq!(metadata.tags, vec!["quality".to_string(), "rust".to_string()]);
+        assert_eq!(metadata.arguments.len(), 2);
+        assert_eq!(metadata.arguments[0].default.as_deref(), Some("main.rs"));
+        assert!(!metadata.arguments[0].required);
+    }
+
+    #[test]
+    fn test_merge_falls_back_to_endpoint_id() {
+        let mut endpoint = endpoint();
+        endpoint.label = String::new();
+
+        let metadata = merge(&endpoint, None);
+        assert_eq!(metadata.title, "review");
+        assert_eq!(metadata.description, "Index description");
+    }
+}
@@ -0,0 +1,383 @@
+use super::parser::AgentLibraryParser;
+use super::storage;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+// リポジトリごとの履歴の合計サイズの上限（超えた場合は古いものから削除する）
+const MAX_HISTORY_BYTES: u64 = 32 << 20;
+// リポジトリごとのスナップショット数の上限
+const MAX_SNAPSHOTS: usize = 500;
+const MANIFEST_FILE: &str = "snapshots.json";
+
+// アプリのデータディレクトリにある履歴の保存先（未設定の場合は記録しない）
+#[cfg(not(test))]
+static HISTORY_ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+#[cfg(test)]
+thread_local! {
+    // テストではスレッドごとに持ち、並行して動く他のテストの書き込みを記録しない
+    static HISTORY_ROOT: RwLock<Option<PathBuf>> = const { RwLock::new(None) };
+}
+// マニフェストの読み書きを直列化する
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+/// 変更の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotKind {
+    Edit,
+    Delete,
+}
+
+/// 変更前のファイルの内容のスナップショット
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub id: String,
+    // リポジトリからの相対パス（`/` 区切り）
+    pub path: String,
+    pub kind: SnapshotKind,
+    pub created_at: String,
+    pub size: u64,
+    pub hash: String,
+}
+
+/// 差分の行の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffTag {
+    Equal,
+    Delete,
+    Insert,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    pub text: String,
+}
+
+/// スナップショットから現在の内容への差分
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub snapshot: Snapshot,
+    // 現在ファイルが無い場合は `false`（すべての行が削除になる）
+    pub current_exists: bool,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    repository: PathBuf,
+    // 古い順
+    snapshots: Vec<Snapshot>,
+}
+
+/// 履歴の保存先（リポジトリごとにサブディレクトリを作る）
+#[derive(Debug, Clone)]
+pub struct HistoryStore {
+    root: PathBuf,
+}
+
+/// アプリが使う履歴の保存先を設定する
+pub fn set_root(root: PathBuf) {
+    with_root(|slot| *slot.write().unwrap_or_else(std::sync::PoisonError::into_inner) = Some(root));
+}
+
+#[cfg(not(test))]
+fn with_root<T>(f: impl FnOnce(&RwLock<Option<PathBuf>>) -> T) -> T {
+    f(&HISTORY_ROOT)
+}
+
+#[cfg(test)]
+fn with_root<T>(f: impl FnOnce(&RwLock<Option<PathBuf>>) -> T) -> T {
+    HISTORY_ROOT.with(f)
+}
+
+/// `.agent_library` 内のファイルの変更前の内容を記録する（保存先が未設定の場合や対象外のファイルは何もしない）
+pub fn record(path: &Path, previous: &[u8], kind: SnapshotKind) -> Result<Option<Snapshot>> {
+    match HistoryStore::global() {
+        Some(store) => store.record(path, previous, kind),
+        None => Ok(None),
+    }
+}
+
+impl HistoryStore {
+    #[must_use] pub const fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// `set_root` で設定した保存先
+    #[must_use] pub fn global() -> Option<Self> {
+        with_root(|slot| slot.read().unwrap_or_else(std::sync::PoisonError::into_inner).clone()).map(Self::new)
+    }
+
+    /// ファイルの変更前の内容を記録する
+    ///
+    /// `.agent_library` の外のファイルと、直前のスナップショットと同じ内容は記録しない
+    pub fn record(&self, path: &Path, previous: &[u8], kind: SnapshotKind) -> Result<Option<Snapshot>> {
+        let Some((repo_path, relative)) = split_repository(path) else {
+            return Ok(None);
+        };
+        let hash = storage::content_hash(previous);
+
+        let _guard = HISTORY_LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let dir = self.repository_dir(&repo_path);
+        let mut manifest = load_manifest(&dir)?;
+        let duplicate = manifest
+            .snapshots
+            .iter()
+            .rev()
+            .find(|s| s.path == relative)
+            .is_some_and(|s| s.hash == hash && kind == SnapshotKind::Edit);
+        if duplicate {
+            return Ok(None);
+        }
+
+        let snapshot = Snapshot {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            path: relative,
+            kind,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            size: previous.len() as u64,
+            hash,
+        };
+        fs::create_dir_all(dir.join("blobs")).with_context(|| format!("Failed to create {}", dir.display()))?;
+        storage::replace_file(&dir.join("blobs").join(&snapshot.id), previous)?;
+        manifest.repository = repo_path;
+        manifest.snapshots.push(snapshot.clone());
+        prune(&dir, &mut manifest);
+        save_manifest(&dir, &manifest)?;
+        tracing::debug!("Recorded snapshot {} of {}", snapshot.id, snapshot.path);
+        Ok(Some(snapshot))
+    }
+
+    /// リポジトリのスナップショット（新しい順）
+    pub fn list(&self, repo_path: &Path) -> Result<Vec<Snapshot>> {
+        let _guard = HISTORY_LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut snapshots = load_manifest(&self.repository_dir(repo_path))?.snapshots;
+        snapshots.reverse();
+        Ok(snapshots)
+    }
+
+    /// スナップショットと現在のファイルの差分
+    pub fn diff(&self, repo_path: &Path, id: &str) -> Result<SnapshotDiff> {
+        let (snapshot, content) = self.read(repo_path, id)?;
+        let target = target_path(repo_path, &snapshot.path)?;
+        let current = fs::read(&target).ok();
+
+        let old = String::from_utf8_lossy(&content);
+        let new = String::from_utf8_lossy(current.as_deref().unwrap_or_default());
+        let lines = TextDiff::from_lines(old.as_ref(), new.as_ref())
+            .iter_all_changes()
+            .map(|change| DiffLine {
+                tag: match change.tag() {
+                    ChangeTag::Equal => DiffTag::Equal,
+                    ChangeTag::Delete => DiffTag::Delete,
+                    ChangeTag::Insert => DiffTag::Insert,
+                },
+                text: change.value().trim_end_matches(['\r', '\n']).to_string(),
+            })
+            .collect();
+        Ok(SnapshotDiff {
+            snapshot,
+            current_exists: current.is_some(),
+            lines,
+        })
+    }
+
+    /// スナップショットの内容をファイルに書き戻す
+    ///
+    /// 書き戻す前の内容も記録するので、元に戻す操作も取り消せる
+    pub fn restore(&self, repo_path: &Path, id: &str) -> Result<Snapshot> {
+        let (snapshot, content) = self.read(repo_path, id)?;
+        let target = target_path(repo_path, &snapshot.path)?;
+        if let Ok(current) = fs::read(&target) {
+            if current != content {
+                self.record(&target, &current, SnapshotKind::Edit)?;
+            }
+        }
+        storage::write_atomic(&target, &content)?;
+        AgentLibraryParser::invalidate(&repo_path.join(".agent_library"));
+        tracing::info!("Restored {} from snapshot {}", snapshot.path, snapshot.id);
+        Ok(snapshot)
+    }
+
+    fn read(&self, repo_path: &Path, id: &str) -> Result<(Snapshot, Vec<u8>)> {
+        let _guard = HISTORY_LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let dir = self.repository_dir(repo_path);
+        let snapshot = load_manifest(&dir)?
+            .snapshots
+            .into_iter()
+            .find(|s| s.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Snapshot '{id}' not found"))?;
+        let blob = dir.join("blobs").join(&snapshot.id);
+        let content = fs::read(&blob).with_context(|| format!("Failed to read {}", blob.display()))?;
+        Ok((snapshot, content))
+    }
+
+    fn repository_dir(&self, repo_path: &Path) -> PathBuf {
+        let canonical = fs::canonicalize(repo_path).unwrap_or_else(|_| repo_path.to_path_buf());
+        let hash = storage::content_hash(canonical.to_string_lossy().as_bytes());
+        self.root.join(&hash[..16])
+    }
+}
+
+/// アプリの履歴の保存先（未設定の場合はエラー）
+pub fn store() -> Result<HistoryStore> {
+    HistoryStore::global().ok_or_else(|| anyhow::anyhow!("History store is not configured"))
+}
+
+// `.agent_library` 内のファイルをリポジトリとリポジトリからの相対パスに分ける
+fn split_repository(path: &Path) -> Option<(PathBuf, String)> {
+    let library = path.ancestors().find(|p| p.file_name().is_some_and(|n| n == ".agent_library"))?;
+    let repo_path = library.parent()?;
+    let relative = path.strip_prefix(repo_path).ok()?;
+    let parts: Vec<_> = relative
+        .components()
+        .map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    let canonical = fs::canonicalize(repo_path).unwrap_or_else(|_| repo_path.to_path_buf());
+    Some((canonical, parts.join("/")))
+}
+
+// スナップショットの書き戻し先（リポジトリの外は指定できない）
+fn target_path(repo_path: &Path, relative: &str) -> Result<PathBuf> {
+    let path = Path::new(relative);
+    if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(anyhow::anyhow!("Invalid snapshot path '{relative}'"));
+    }
+    Ok(repo_path.join(path))
+}
+
+fn load_manifest(dir: &Path) -> Result<Manifest> {
+    let path = dir.join(MANIFEST_FILE);
+    if !path.is_file() {
+        return Ok(Manifest::default());
+    }
+    let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn save_manifest(dir: &Path, manifest: &Manifest) -> Result<()> {
+    let content = serde_json::to_string_pretty(manifest)?;
+    storage::replace_file(&dir.join(MANIFEST_FILE), content.as_bytes())
+}
+
+// 上限を超えた分を古いものから削除する（最新のスナップショットは必ず残す）
+fn prune(dir: &Path, manifest: &mut Manifest) {
+    let mut total: u64 = manifest.snapshots.iter().map(|s| s.size).sum();
+    let mut removed = 0;
+    while manifest.snapshots.len() - removed > 1
+        && (total > MAX_HISTORY_BYTES || manifest.snapshots.len() - removed > MAX_SNAPSHOTS)
+    {
+        let snapshot = &manifest.snapshots[removed];
+        total -= snapshot.size;
+        let _ = fs::remove_file(dir.join("blobs").join(&snapshot.id));
+        removed += 1;
+    }
+    manifest.snapshots.drain(..removed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, PathBuf, HistoryStore) {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("repo");
+        fs::create_dir_all(repo.join(".agent_library/prompts")).unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history"));
+        (temp_dir, repo, store)
+    }
+
+    #[test]
+    fn test_record_diff_and_restore() {
+        let (_temp_dir, repo, store) = setup();
+        let path = repo.join(".agent_library/prompts/review.md");
+        fs::write(&path, "# Review\nold line\n").unwrap();
+
+        let snapshot = store.record(&path, b"# Review\nold line\n", SnapshotKind::Edit).unwrap().unwrap();
+        assert_eq!(snapshot.path, ".agent_library/prompts/review.md");
+        // 同じ内容は続けて記録しない、`.agent_library` の外は記録しない
+        assert!(store.record(&path, b"# Review\nold line\n", SnapshotKind::Edit).unwrap().is_none());
+        assert!(store.record(&repo.join("README.md"), b"x", SnapshotKind::Edit).unwrap().is_none());
+
+        fs::write(&path, "# Review\nnew line\n").unwrap();
+        let diff = store.diff(&repo, &snapshot.id).unwrap();
+        assert!(diff.current_exists);
+        let changes: Vec<_> = diff.lines.iter().map(|l| (l.tag, l.text.as_str())).collect();
+        assert_eq!(
+            changes,
+            vec![(DiffTag::Equal, "# Review"), (DiffTag::Delete, "old line"), (DiffTag::Insert, "new line")]
+        );
+
+        store.restore(&repo, &snapshot.id).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# Review\nold line\n");
+        // 書き戻す前の内容も記録される
+        let snapshots = store.list(&repo).unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert!(store.restore(&repo, "missing").is_err());
+
+        // 削除したファイルも戻せる
+        fs::remove_file(&path).unwrap();
+        let restored = store.restore(&repo, &snapshots[0].id).unwrap();
+        assert_eq!(restored.id, snapshots[0].id);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# Review\nnew line\n");
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let (_temp_dir, repo, store) = setup();
+        let path = repo.join(".agent_library/prompts/review.md");
+        for i in 0..=MAX_SNAPSHOTS {
+            store.record(&path, format!("version {i}").as_bytes(), SnapshotKind::Edit).unwrap();
+        }
+        let snapshots = store.list(&repo).unwrap();
+        assert_eq!(snapshots.len(), MAX_SNAPSHOTS);
+        assert_eq!(store.read(&repo, &snapshots[0].id).unwrap().1, format!("version {MAX_SNAPSHOTS}").as_bytes());
+
+        let large = vec![b'x'; usize::try_from(MAX_HISTORY_BYTES).unwrap()];
+        store.record(&path, &large, SnapshotKind::Delete).unwrap();
+        let snapshots = store.list(&repo).unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].kind, SnapshotKind::Delete);
+        let blobs = fs::read_dir(store.repository_dir(&repo).join("blobs")).unwrap().count();
+        assert_eq!(blobs, 1);
+    }
+
+    #[test]
+    fn test_history_root_under_agent_library() {
+        let (temp_dir, repo, _) = setup();
+        let root = temp_dir.path().join("home/.agent_library/app/history");
+        let path = repo.join(".agent_library/prompts/review.md");
+        fs::write(&path, "v1").unwrap();
+
+        // 履歴自体の書き込みが再び記録されるとロックが二重に取られて止まる
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let thread_root = root.clone();
+        std::thread::spawn(move || {
+            set_root(thread_root);
+            let result = ["v2", "v3", "v4"]
+                .iter()
+                .try_for_each(|content| storage::write_atomic(&path, content.as_bytes()));
+            sender.send(result.map_err(|e| e.to_string())).unwrap();
+        });
+        receiver.recv_timeout(std::time::Duration::from_secs(10)).unwrap().unwrap();
+
+        let store = HistoryStore::new(root);
+        let contents: Vec<_> = store
+            .list(&repo)
+            .unwrap()
+            .iter()
+            .map(|s| store.read(&repo, &s.id).unwrap().1)
+            .collect();
+        assert_eq!(contents, vec![b"v3".to_vec(), b"v2".to_vec(), b"v1".to_vec()]);
+    }
+}
@@ -0,0 +1,480 @@
+use super::parser::AgentLibraryParser;
+use super::types::{AgentIndex, McpEndpoint, PromptArgument};
+use super::{exporter, front_matter, index_file, template};
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+use walkdir::WalkDir;
+
+// Claude Code の `$ARGUMENTS` / `$1` 〜 `$9`
+static CLAUDE_PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$(ARGUMENTS|[1-9])").unwrap());
+// `argument-hint` の `[file]` / `<focus>`
+static ARGUMENT_HINT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[\[<]([^\]>]+)[\]>]").unwrap());
+// VS Code の `${input:name}` / `${input:name:placeholder}`
+static GITHUB_INPUT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$\{input:([^}:]+)(?::([^}]*))?\}").unwrap());
+static GITHUB_VARIABLE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$\{(\w+)\}").unwrap());
+// `@src/main.rs` のようなファイル参照
+static FILE_REFERENCE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)(?:^|\s)@[\w./-]*[\w/]").unwrap());
+
+/// 他ツールのプロンプト形式（取り込みと書き出しで共通）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ToolFormat {
+    // `.claude/commands/**/*.md`
+    ClaudeCommands,
+    // `.cursor/rules/**/*.mdc` と `.cursorrules`
+    CursorRules,
+    // `.github/prompts/**/*.prompt.md`
+    GithubPrompts,
+}
+
+impl ToolFormat {
+    pub const ALL: [Self; 3] = [Self::ClaudeCommands, Self::CursorRules, Self::GithubPrompts];
+
+    // 取り込んだプロンプトを置くディレクトリ（.agent_library からの相対パス）
+    const fn directory(self) -> &'static str {
+        match self {
+            Self::ClaudeCommands => "prompts/imported/claude",
+            Self::CursorRules => "prompts/imported/cursor",
+            Self::GithubPrompts => "prompts/imported/github",
+        }
+    }
+
+    const fn tag(self) -> &'static str {
+        match self {
+            Self::ClaudeCommands => "claude-code",
+            Self::CursorRules => "cursor",
+            Self::GithubPrompts => "copilot",
+        }
+    }
+}
+
+/// 取り込んだプロンプト
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportedPrompt {
+    pub id: String,
+    // リポジトリからの相対パス
+    pub source: String,
+    // .agent_library からの相対パス
+    pub prompt_file: String,
+    pub arguments: Vec<String>,
+}
+
+/// 変換できなかった内容
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportIssue {
+    pub source: String,
+    pub message: String,
+}
+
+/// 取り込みの結果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub imported: Vec<ImportedPrompt>,
+    // 取り込んだが一部を変換できなかったもの
+    pub warnings: Vec<ImportIssue>,
+    // 取り込まなかったファイル
+    pub skipped: Vec<ImportIssue>,
+}
+
+// 変換したプロンプト
+struct Converted {
+    endpoint: McpEndpoint,
+    body: String,
+    warnings: Vec<String>,
+}
+
+/// リポジトリ内の他ツールのプロンプトを `.agent_library` のエンドポイントとして取り込む
+///
+/// `formats` が空の場合はすべての形式を対象にする。既存のIDやファイルと重複するものは取り込まない。
+/// `dry_run` の場合は結果だけを返し、ファイルは書き込まない
+pub fn import_prompts(repo_path: &Path, formats: &[ToolFormat], dry_run: bool) -> Result<ImportSummary> {
+    let formats = if formats.is_empty() { &ToolFormat::ALL[..] } else { formats };
+    let agent_lib_path = repo_path.join(".agent_library");
+    let index_path = agent_lib_path.join("agent_index.yml");
+
+    let mut taken_ids: HashSet<String> = if index_path.is_file() {
+        let content = fs::read_to_string(&index_path)
+            .with_context(|| format!("Failed to read {}", index_path.display()))?;
+        let index: AgentIndex = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", index_path.display()))?;
+        index
+            .mcp_endpoints
+            .into_iter()
+            .flat_map(|e| std::iter::once(e.id).chain(e.aliases))
+            .collect()
+    } else {
+        HashSet::new()
+    };
+
+    let mut summary = ImportSummary::default();
+    let mut files = Vec::new();
+    for &format in formats {
+        for source in find_sources(repo_path, format) {
+            let issue = |message: String| ImportIssue {
+                source: source.clone(),
+                message,
+            };
+            let converted = fs::read_to_string(repo_path.join(&source))
+                .with_context(|| format!("Failed to read {source}"))
+                .and_then(|content| convert(format, &source, &content));
+            let Converted { endpoint, body, warnings } = match converted {
+                Ok(converted) => converted,
+                Err(e) => {
+                    summary.skipped.push(issue(format!("{e:#}")));
+                    continue;
+                }
+            };
+
+            if taken_ids.contains(&endpoint.id) {
+                summary.skipped.push(issue(format!("Endpoint id '{}' already exists", endpoint.id)));
+                continue;
+            }
+            if agent_lib_path.join(&endpoint.prompt_file).exists() {
+                summary.skipped.push(issue(format!("{} already exists", endpoint.prompt_file)));
+                continue;
+            }
+            taken_ids.insert(endpoint.id.clone());
+
+            summary.warnings.extend(warnings.into_iter().map(issue));
+            summary.imported.push(ImportedPrompt {
+                id: endpoint.id.clone(),
+                source: source.clone(),
+                prompt_file: endpoint.prompt_file.clone(),
+                arguments: endpoint.arguments.iter().map(|a| a.name.clone()).collect(),
+            });
+            files.push((endpoint, body));
+        }
+    }
+
+    if dry_run || files.is_empty() {
+        return Ok(summary);
+    }
+
+    for (endpoint, body) in &files {
+        let path = agent_lib_path.join(&endpoint.prompt_file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&path, body).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    index_file::upsert_endpoints(&index_path, files.iter().map(|(e, _)| index_file::endpoint_value(e)).collect())?;
+
+    AgentLibraryParser::invalidate(&agent_lib_path);
+    tracing::info!("Imported {} prompts into {}", summary.imported.len(), agent_lib_path.display());
+    Ok(summary)
+}
+
+// 形式ごとの取り込み元ファイル（リポジトリからの相対パス、名前順）
+fn find_sources(repo_path: &Path, format: ToolFormat) -> Vec<String> {
+    let (directory, matches): (&str, fn(&str) -> bool) = match format {
+        ToolFormat::ClaudeCommands => (".claude/commands", |name| name.ends_with(".md")),
+        ToolFormat::CursorRules => (".cursor/rules", |name| name.ends_with(".mdc") || name.ends_with(".md")),
+        ToolFormat::GithubPrompts => (".github/prompts", |name| name.ends_with(".prompt.md")),
+    };
+
+    let mut sources: Vec<String> = WalkDir::new(repo_path.join(directory))
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+        .filter(|entry| entry.file_type().is_file() && matches(&entry.file_name().to_string_lossy()))
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(repo_path).ok()?;
+            Some(relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+        })
+        .collect();
+    if format == ToolFormat::CursorRules && repo_path.join(".cursorrules").is_file() {
+        sources.push(".cursorrules".to_string());
+    }
+    sources.sort();
+    sources
+}
+
+// 1つのファイルをエンドポイントと本文に変換する
+fn convert(format: ToolFormat, source: &str, content: &str) -> Result<Converted> {
+    if exporter::is_generated(content) {
+        return Err(anyhow::anyhow!("File was generated from an agent library"));
+    }
+    let (mut front_matter, body) = parse_front_matter(content);
+    let mut warnings = Vec::new();
+
+    // 形式ごとのディレクトリからの相対パスの各段（拡張子を除く）
+    let segments: Vec<String> = if source == ".cursorrules" {
+        vec!["cursorrules".to_string()]
+    } else {
+        let relative = source.splitn(3, '/').nth(2).unwrap_or(source);
+        let relative = [".prompt.md", ".mdc", ".md"]
+            .iter()
+            .find_map(|ext| relative.strip_suffix(ext))
+            .unwrap_or(relative);
+        relative.split('/').map(ToString::to_string).collect()
+    };
+    let id = segments.iter().map(|s| sanitize_id(s)).collect::<Vec<_>>().join("_");
+    if id.is_empty() {
+        return Err(anyhow::anyhow!("Could not derive an endpoint id from the file name"));
+    }
+
+    let (body, arguments, trigger) = match format {
+        ToolFormat::ClaudeCommands => {
+            let hint = take_string(&mut front_matter, "argument-hint");
+            let (body, arguments) = convert_claude_placeholders(body, hint.as_deref());
+            if body.lines().any(|line| line.trim_start().starts_with("!`")) {
+                warnings.push("Bash command execution (!`...`) is not supported and was kept as text".to_string());
+            }
+            (body, arguments, Some(format!("/{}", segments.join(":"))))
+        }
+        ToolFormat::CursorRules => (body.to_string(), Vec::new(), None),
+        ToolFormat::GithubPrompts => {
+            let (body, arguments) = convert_github_inputs(body);
+            let mut unsupported: Vec<&str> = GITHUB_VARIABLE
+                .captures_iter(&body)
+                .filter_map(|c| c.get(1).map(|m| m.as_str()))
+                .collect();
+            unsupported.sort_unstable();
+            unsupported.dedup();
+            for variable in unsupported {
+                warnings.push(format!("Variable '${{{variable}}}' is not supported and was kept as text"));
+            }
+            (body, arguments, None)
+        }
+    };
+    if FILE_REFERENCE.is_match(&body) {
+        warnings.push("File references (@path) are not expanded and were kept as text".to_string());
+    }
+
+    let label = take_string(&mut front_matter, "name")
+        .or_else(|| take_string(&mut front_matter, "title"))
+        .unwrap_or_else(|| segments.last().cloned().unwrap_or_else(|| id.clone()));
+    let description = take_string(&mut front_matter, "description").unwrap_or_else(|| first_line(&body));
+    for key in front_matter.keys() {
+        warnings.push(format!("Front matter key '{key}' is not supported and was dropped"));
+    }
+
+    if let Err(e) = template::parse(&body) {
+        return Err(anyhow::anyhow!("Converted prompt is not a valid template: {e}"));
+    }
+
+    let body = if body.ends_with('\n') { body } else { format!("{body}\n") };
+    Ok(Converted {
+        endpoint: McpEndpoint {
+            prompt_file: format!("{}/{id}.md", format.directory()),
+            id,
+            label,
+            description,
+            trigger,
+            tags: vec![format.tag().to_string()],
+            arguments,
+            ..Default::default()
+        },
+        body,
+        warnings,
+    })
+}
+
+// front matter をキーごとに読み込む
+//
+// Cursor の `globs: *.ts` のように YAML として不正な値もあるため、読めない場合は `key: value` の行単位で読む
+fn parse_front_matter(content: &str) -> (BTreeMap<String, serde_yaml::Value>, &str) {
+    let Some((yaml, body)) = front_matter::split_raw(content) else {
+        return (BTreeMap::new(), content.strip_prefix('\u{feff}').unwrap_or(content));
+    };
+
+    let fields = serde_yaml::from_str::<BTreeMap<String, serde_yaml::Value>>(yaml).unwrap_or_else(|_| {
+        yaml.lines()
+            .filter_map(|line| line.split_once(':'))
+            .filter(|(key, _)| !key.starts_with([' ', '\t', '-']))
+            .map(|(key, value)| (key.trim().to_string(), serde_yaml::Value::String(value.trim().to_string())))
+            .collect()
+    });
+    (fields, body)
+}
+
+fn take_string(front_matter: &mut BTreeMap<String, serde_yaml::Value>, key: &str) -> Option<String> {
+    let value = front_matter.remove(key)?;
+    let text = match value {
+        serde_yaml::Value::String(text) => text,
+        serde_yaml::Value::Null => return None,
+        other => serde_yaml::to_string(&other).ok()?.trim().to_string(),
+    };
+    Some(text.trim().trim_matches(['"', '\'']).to_string()).filter(|t| !t.is_empty())
+}
+
+// `$ARGUMENTS` は `{{arguments}}`、`$1` 以降は `argument-hint` の名前（無ければ `arg1`）にする
+fn convert_claude_placeholders(body: &str, hint: Option<&str>) -> (String, Vec<PromptArgument>) {
+    let hint_names: Vec<&str> = hint
+        .map(|hint| {
+            let bracketed: Vec<&str> = ARGUMENT_HINT
+                .captures_iter(hint)
+                .filter_map(|c| c.get(1).map(|m| m.as_str()))
+                .collect();
+            if bracketed.is_empty() { hint.split_whitespace().collect() } else { bracketed }
+        })
+        .unwrap_or_default();
+
+    let mut arguments: Vec<PromptArgument> = Vec::new();
+    let converted = CLAUDE_PLACEHOLDER.replace_all(body, |caps: &Captures| {
+        let (name, description) = match &caps[1] {
+            "ARGUMENTS" => ("arguments".to_string(), hint.map(ToString::to_string)),
+            position => {
+                let position: usize = position.parse().unwrap_or(1);
+                let hinted = hint_names.get(position - 1).map(|name| argument_name(name));
+                let name = hinted
+                    .filter(|name| !name.is_empty() && name != "arguments")
+                    .unwrap_or_else(|| format!("arg{position}"));
+                (name, hint_names.get(position - 1).map(ToString::to_string))
+            }
+        };
+        if !arguments.iter().any(|a| a.name == name) {
+            arguments.push(PromptArgument {
+                name: name.clone(),
+                description,
+                ..Default::default()
+            });
+        }
+        format!("{{{{{name}}}}}")
+    });
+    (converted.into_owned(), arguments)
+}
+
+// `${input:name:placeholder}` を `{{name}}` にし、placeholder を引数の説明にする
+fn convert_github_inputs(body: &str) -> (String, Vec<PromptArgument>) {
+    let mut arguments: Vec<PromptArgument> = Vec::new();
+    let converted = GITHUB_INPUT.replace_all(body, |caps: &Captures| {
+        let name = argument_name(&caps[1]);
+        if name.is_empty() {
+            return caps[0].to_string();
+        }
+        if !arguments.iter().any(|a| a.name == name) {
+            arguments.push(PromptArgument {
+                name: name.clone(),
+                description: caps.get(2).map(|m| m.as_str().trim().to_string()).filter(|d| !d.is_empty()),
+                ..Default::default()
+            });
+        }
+        format!("{{{{{name}}}}}")
+    });
+    (converted.into_owned(), arguments)
+}
+
+// プレースホルダーに使える引数名にする
+fn argument_name(raw: &str) -> String {
+    raw.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect::<String>()
+        .trim_matches('_')
+        .to_string()
+}
+
+fn sanitize_id(segment: &str) -> String {
+    segment
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect::<String>()
+        .trim_matches('_')
+        .to_string()
+}
+
+// 説明が無い場合は本文の最初の行を使う
+fn first_line(body: &str) -> String {
+    body.lines()
+        .map(|line| line.trim().trim_start_matches('#').trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(repo: &Path, relative: &str, content: &str) {
+        let path = repo.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_convert_claude_command() {
+        let source = "---\ndescription: Review a file\nargument-hint: [file] [focus]\nallowed-tools: Bash(git diff:*)\n---\n\nReview $1 focusing on $2.\n\nExtra: $ARGUMENTS\n";
+        let converted = convert(ToolFormat::ClaudeCommands, ".claude/commands/frontend/review.md", source).unwrap();
+
+        assert_eq!(converted.endpoint.id, "frontend_review");
+        assert_eq!(converted.endpoint.trigger.as_deref(), Some("/frontend:review"));
+        assert_eq!(converted.endpoint.description, "Review a file");
+        assert_eq!(converted.endpoint.prompt_file, "prompts/imported/claude/frontend_review.md");
+        assert_eq!(converted.body, "Review {{file}} focusing on {{focus}}.\n\nExtra: {{arguments}}\n");
+        let names: Vec<&str> = converted.endpoint.arguments.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["file", "focus", "arguments"]);
+        assert_eq!(converted.warnings, vec!["Front matter key 'allowed-tools' is not supported and was dropped"]);
+    }
+
+    #[test]
+    fn test_convert_cursor_rule_and_github_prompt() {
+        // `globs: *.ts` は YAML として不正だが行単位で読める
+        let rule = "---\ndescription: TypeScript style\nglobs: *.ts\nalwaysApply: false\n---\nUse strict mode. See @docs/style.md\n";
+        let converted = convert(ToolFormat::CursorRules, ".cursor/rules/typescript.mdc", rule).unwrap();
+        assert_eq!(converted.endpoint.id, "typescript");
+        assert_eq!(converted.endpoint.description, "TypeScript style");
+        assert_eq!(converted.warnings.len(), 3);
+        assert!(converted.warnings[0].contains("@path"));
+
+        let prompt = "---\nmode: agent\ndescription: Generate a form\n---\nCreate ${input:formName:Name of the form} using ${selection}.\n";
+        let converted = convert(ToolFormat::GithubPrompts, ".github/prompts/react-form.prompt.md", prompt).unwrap();
+        assert_eq!(converted.endpoint.id, "react-form");
+        assert_eq!(converted.body, "Create {{formname}} using ${selection}.\n");
+        assert_eq!(converted.endpoint.arguments[0].description.as_deref(), Some("Name of the form"));
+        assert_eq!(
+            converted.warnings,
+            vec![
+                "Variable '${selection}' is not supported and was kept as text",
+                "Front matter key 'mode' is not supported and was dropped",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_prompts_into_library() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path();
+        write(repo, ".agent_library/agent_index.yml", "name: Team\nmcp_endpoints:\n  - id: commit\n    label: Commit\n    prompt_file: commit.md\n");
+        write(repo, ".agent_library/commit.md", "# Commit");
+        write(repo, ".claude/commands/review.md", "# Review the diff\n\nFocus on $ARGUMENTS\n");
+        write(repo, ".claude/commands/commit.md", "Write a commit message");
+        write(repo, ".github/prompts/broken.prompt.md", "{{#if open}} never closed");
+        write(repo, ".cursorrules", "Always write tests.");
+        // 書き出したファイルは取り込まない
+        write(repo, ".claude/commands/generated.md", "<!-- Generated by Librarian from agent library prompt 'x'. -->\nBody");
+
+        let preview = import_prompts(repo, &[], true).unwrap();
+        assert_eq!(preview.imported.len(), 2);
+        assert!(!repo.join(".agent_library/prompts").exists());
+
+        let summary = import_prompts(repo, &[], false).unwrap();
+        let ids: Vec<&str> = summary.imported.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["review", "cursorrules"]);
+        let skipped: Vec<&str> = summary.skipped.iter().map(|s| s.source.as_str()).collect();
+        assert_eq!(
+            skipped,
+            vec![".claude/commands/commit.md", ".claude/commands/generated.md", ".github/prompts/broken.prompt.md"]
+        );
+
+        let library = AgentLibraryParser::parse(repo).unwrap();
+        let review = library.find_prompt("review").unwrap();
+        assert_eq!(review.description, "Review the diff");
+        assert_eq!(review.trigger.as_deref(), Some("/review"));
+        assert_eq!(review.tags, vec!["claude-code".to_string()]);
+        assert_eq!(review.arguments[0].name, "arguments");
+        assert_eq!(library.index.name.as_deref(), Some("Team"));
+
+        // 2回目は既存のIDとしてすべてスキップされる
+        let again = import_prompts(repo, &[ToolFormat::ClaudeCommands], false).unwrap();
+        assert!(again.imported.is_empty());
+        assert_eq!(again.skipped.len(), 3);
+    }
+}
@@ -0,0 +1,169 @@
+use super::front_matter;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const INCLUDE_OPEN: &str = "{{>";
+const INCLUDE_CLOSE: &str = "}}";
+
+/// インクルード展開の結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Expansion {
+    pub content: String,
+    // 展開したファイル（重複なし、初出順）
+    pub included_files: Vec<PathBuf>,
+}
+
+/// `{{> shared/style.md}}` 形式のインクルードを展開する
+///
+/// パスは `.agent_library` からの相対パスで、ライブラリ外のファイルは読み込めない。
+/// インクルードされたファイルの front matter は取り除かれる
+pub fn expand_includes(content: &str, file: &Path, agent_lib_path: &Path) -> Result<Expansion> {
+    let root = agent_lib_path
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", agent_lib_path.display()))?;
+    let current = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+
+    let mut expansion = Expansion::default();
+    let mut stack = vec![current];
+    expansion.content = expand(content, &root, &mut stack, &mut expansion.included_files)?;
+    Ok(expansion)
+}
+
+fn expand(content: &str, root: &Path, stack: &mut Vec<PathBuf>, included: &mut Vec<PathBuf>) -> Result<String> {
+    let mut output = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find(INCLUDE_OPEN) {
+        output.push_str(&rest[..start]);
+        let after_open = &rest[start + INCLUDE_OPEN.len()..];
+        let Some(end) = after_open.find(INCLUDE_CLOSE) else {
+            output.push_str(&rest[start..]);
+            return Ok(output);
+        };
+
+        let target = after_open[..end].trim();
+        let path = resolve_target(target, root, stack)?;
+
+        if let Some(position) = stack.iter().position(|p| *p == path) {
+            let chain = stack[position..]
+                .iter()
+                .chain(std::iter::once(&path))
+                .map(|p| display_relative(p, root))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(anyhow::anyhow!("Include cycle detected: {chain}"));
+        }
+
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read included file {}", path.display()))?;
+        let (_, body) = front_matter::split_front_matter(&source)
+            .with_context(|| format!("Invalid front matter in {}", path.display()))?;
+
+        if !included.contains(&path) {
+            included.push(path.clone());
+        }
+
+        stack.push(path);
+        let expanded = expand(body, root, stack, included)?;
+        stack.pop();
+
+        output.push_str(expanded.trim_end_matches(['\r', '\n']));
+        rest = &after_open[end + INCLUDE_CLOSE.len()..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn resolve_target(target: &str, root: &Path, stack: &[PathBuf]) -> Result<PathBuf> {
+    let including = stack
+        .last()
+        .map_or_else(String::new, |p| display_relative(p, root));
+
+    if target.is_empty() {
+        return Err(anyhow::anyhow!("Empty include directive in {including}"));
+    }
+
+    let candidate = root.join(target);
+    let path = candidate.canonicalize().map_err(|_| {
+        anyhow::anyhow!("Included file '{target}' not found (included from {including})")
+    })?;
+
+    if !path.starts_with(root) {
+        return Err(anyhow::anyhow!(
+            "Included file '{target}' is outside .agent_library (included from {including})"
+        ));
+    }
+
+    Ok(path)
+}
+
+fn display_relative(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_expand_nested_includes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(root, "shared/style.md", "---\ntitle: Style\n---\nFollow the style guide.\n{{> shared/format.md}}\n");
+        write(root, "shared/format.md", "Answer in Markdown.\n");
+        let prompt = write(root, "review.md", "# Review\n\n{{> shared/style.md}}\n\nDone.");
+
+        let expansion = expand_includes(&fs::read_to_string(&prompt).unwrap(), &prompt, root).unwrap();
+        assert_eq!(
+            expansion.content,
+            "# Review\n\nFollow the style guide.\nAnswer in Markdown.\n\nDone."
+        );
+        assert_eq!(expansion.included_files.len(), 2);
+    }
+
+    #[test]
+    fn test_expand_detects_cycles() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(root, "a.md", "{{> b.md}}");
+        write(root, "b.md", "{{> a.md}}");
+        let prompt = root.join("a.md");
+
+        let error = expand_includes("{{> b.md}}", &prompt, root).unwrap_err();
+        assert_eq!(error.to_string(), "Include cycle detected: a.md -> b.md -> a.md");
+    }
+
+    #[test]
+    fn test_expand_missing_include() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let prompt = write(root, "review.md", "{{> shared/missing.md}}");
+
+        let error = expand_includes("{{> shared/missing.md}}", &prompt, root).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Included file 'shared/missing.md' not found (included from review.md)"
+        );
+    }
+
+    #[test]
+    fn test_expand_rejects_files_outside_library() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join(".agent_library");
+        write(temp_dir.path(), "secret.md", "secret");
+        let prompt = write(&root, "review.md", "{{> ../secret.md}}");
+
+        let error = expand_includes("{{> ../secret.md}}", &prompt, &root).unwrap_err();
+        assert!(error.to_string().contains("outside .agent_library"));
+    }
+}
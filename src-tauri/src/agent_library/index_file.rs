@@ -0,0 +1,712 @@
+use super::storage;
+use super::types::{AgentIndex, McpEndpoint};
+use anyhow::{Context, Result};
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 項目が無い場合の `mcp_endpoints` の項目のインデント
+const DEFAULT_ITEM_INDENT: usize = 2;
+
+/// コメントや書式を保ったまま編集する `agent_index.yml`
+///
+/// `mcp_endpoints` の項目を行単位で追加・削除・並べ替えし、それ以外の行はそのまま残す
+#[derive(Debug, Clone)]
+pub struct IndexDocument {
+    path: PathBuf,
+    lines: Vec<String>,
+    crlf: bool,
+}
+
+// `mcp_endpoints:` のブロック
+struct Block {
+    key: usize,
+    // ブロックの最後の内容行の次の行
+    end: usize,
+    indent: usize,
+    // `mcp_endpoints: []`
+    flow_empty: bool,
+    items: Vec<Item>,
+}
+
+// 1つのエンドポイント（直前のコメントを含み、後ろの空行は含まない）
+#[derive(Clone, Copy)]
+struct Item {
+    start: usize,
+    end: usize,
+}
+
+impl IndexDocument {
+    /// ファイルを読み込む。ファイルが無い場合は空のインデックスとして扱う
+    pub fn load(index_path: &Path) -> Result<Self> {
+        let text = if index_path.is_file() {
+            fs::read_to_string(index_path).with_context(|| format!("Failed to read {}", index_path.display()))?
+        } else {
+            String::new()
+        };
+        Ok(Self {
+            path: index_path.to_path_buf(),
+            lines: text.lines().map(ToString::to_string).collect(),
+            crlf: text.contains("\r\n"),
+        })
+    }
+
+    #[must_use] pub fn text(&self) -> String {
+        let newline = if self.crlf { "\r\n" } else { "\n" };
+        let mut text = self.lines.join(newline);
+        text.push_str(newline);
+        text
+    }
+
+    /// 現在の内容を解析する
+    pub fn index(&self) -> Result<AgentIndex> {
+        let text = self.text();
+        if text.trim().is_empty() {
+            return Ok(AgentIndex::default());
+        }
+        serde_yaml::from_str(&text).with_context(|| format!("Failed to parse {}", self.path.display()))
+    }
+
+    /// 内容が正しい YAML であることを確認してから書き込む
+    pub fn save(&self) -> Result<()> {
+        self.index()?;
+        storage::write_atomic(&self.path, self.text().as_bytes())
+    }
+
+    /// エンドポイントを追加する（`after` を指定した場合はそのエンドポイントの後ろ、それ以外は末尾）
+    pub fn insert_endpoint(&mut self, value: &Value, after: Option<&str>) -> Result<()> {
+        let block = self.editable_list("mcp_endpoints")?;
+
+        let position = match after {
+            Some(id) => block.items[self.position(&block, id)?].end,
+            None => block.end,
+        };
+        let separated = block.items.windows(2).any(|w| w[0].end < w[1].start);
+        let mut lines = item_lines(value, block.indent)?;
+        if separated && !block.items.is_empty() {
+            lines.insert(0, String::new());
+        }
+        self.lines.splice(position..position, lines);
+        Ok(())
+    }
+
+    /// エンドポイントを追加し、置き換えたエンドポイントのIDを返す
+    ///
+    /// 同じIDのエントリだけを置き換え、別名を含めて他のエントリとIDが重なる場合はエラーにする
+    pub fn upsert_endpoints(&mut self, incoming: Vec<Value>) -> Result<Vec<String>> {
+        let mut replaced = Vec::new();
+        for endpoint in incoming {
+            let id = endpoint.get("id").and_then(Value::as_str).context("Endpoint without id")?.to_string();
+            if self.index()?.mcp_endpoints.iter().any(|e| e.id == id) {
+                self.remove_endpoint(&id)?;
+                replaced.push(id.clone());
+            }
+            let names = endpoint_names(&endpoint);
+            if let Some(existing) = self
+                .index()?
+                .mcp_endpoints
+                .into_iter()
+                .find(|e| names.contains(&e.id) || e.aliases.iter().any(|a| names.contains(a)))
+            {
+                return Err(anyhow::anyhow!("Endpoint '{id}' conflicts with the id or aliases of '{}'", existing.id));
+            }
+            self.insert_endpoint(&endpoint, None)?;
+        }
+        Ok(replaced)
+    }
+
+    /// トップレベルの `include`・`exclude`・`resources`・`lint` に値を追加し、変更したかどうかを返す
+    ///
+    /// 一覧は無い項目だけを末尾に追加し（`resources` は `path` で比較する）、`lint` は無いキーだけを追加する。
+    /// ブロック形式の一覧への追加ではコメントを保ち、それ以外はそのキーの値だけを書き直す
+    pub fn merge_section(&mut self, name: &str, incoming: &Value) -> Result<bool> {
+        let current = self.value()?.get(name).cloned().unwrap_or(Value::Null);
+        let merged = if current.is_null() { incoming.clone() } else { merge_values(&current, incoming) };
+        if merged == current || merged.is_null() {
+            return Ok(false);
+        }
+
+        let appended = match (&current, &merged) {
+            (Value::Null | Value::Sequence(_), Value::Sequence(items)) => {
+                let existing = current.as_sequence().map_or(0, Vec::len);
+                match self.list_block(name) {
+                    Ok(_) => {
+                        let block = self.editable_list(name)?;
+                        let mut lines = Vec::new();
+                        for item in &items[existing..] {
+                            lines.extend(item_lines(item, block.indent)?);
+                        }
+                        self.lines.splice(block.end..block.end, lines);
+                        true
+                    }
+                    Err(_) => false,
+                }
+            }
+            _ => false,
+        };
+        if !appended {
+            let mut mapping = Mapping::new();
+            mapping.insert(name.into(), merged);
+            let lines: Vec<String> = serde_yaml::to_string(&Value::Mapping(mapping))?.lines().map(ToString::to_string).collect();
+            match self.key_position(name) {
+                Some(key) => {
+                    let end = self.section_end(key);
+                    self.lines.splice(key..end, lines);
+                }
+                None => self.append_section(lines),
+            }
+        }
+        Ok(true)
+    }
+
+    /// エンドポイントを削除する
+    pub fn remove_endpoint(&mut self, id: &str) -> Result<()> {
+        let block = self.block()?.with_context(|| format!("Endpoint '{id}' not found"))?;
+        let index = self.position(&block, id)?;
+        let item = block.items[index];
+        // 後ろの空行も削除する（最後の項目の場合は前の空行）
+        let range = match block.items.get(index + 1) {
+            Some(next) => item.start..next.start,
+            None => index.checked_sub(1).map_or(item.start, |i| block.items[i].end)..item.end,
+        };
+        self.lines.drain(range);
+        Ok(())
+    }
+
+    /// エンドポイントを `order` の順に並べ替える（区切りの空行は元の位置に残す）
+    pub fn reorder_endpoints(&mut self, order: &[String]) -> Result<()> {
+        let Some(block) = self.block()? else {
+            return Ok(());
+        };
+        let ids = self.ids(&block)?;
+        let mut sorted = order.to_vec();
+        sorted.sort();
+        let mut current = ids.clone();
+        current.sort();
+        if sorted != current {
+            return Err(anyhow::anyhow!("Order must list every endpoint in agent_index.yml exactly once"));
+        }
+
+        let texts: Vec<Vec<String>> = order
+            .iter()
+            .map(|id| {
+                let item = block.items[ids.iter().position(|i| i == id).unwrap_or_default()];
+                self.lines[item.start..item.end].to_vec()
+            })
+            .collect();
+        let mut lines = Vec::new();
+        for (i, text) in texts.into_iter().enumerate() {
+            lines.extend(text);
+            if let Some(next) = block.items.get(i + 1) {
+                lines.extend_from_slice(&self.lines[block.items[i].end..next.start]);
+            }
+        }
+        let start = block.items.first().map_or(block.end, |item| item.start);
+        self.lines.splice(start..block.end, lines);
+        Ok(())
+    }
+
+    /// エンドポイントの `key: value` の値を書き換える（キーが無い場合は追加する）
+    pub fn set_value(&mut self, id: &str, key: &str, value: &str) -> Result<()> {
+        let block = self.block()?.with_context(|| format!("Endpoint '{id}' not found"))?;
+        let item = block.items[self.position(&block, id)?];
+        let (content_indent, first) = self.content_indent(item)?;
+
+        match self.key_line(item, key)? {
+            Some(line) => {
+                let (prefix, rest) = split_key(&self.lines[line], key).context("Failed to locate key")?;
+                let (old, comment) = split_comment(rest);
+                self.lines[line] = format!("{prefix}{key}: {}{comment}", format_scalar(value, old.trim()));
+            }
+            None => {
+                self.lines.insert(first + 1, format!("{}{key}: {}", " ".repeat(content_indent), format_scalar(value, "")));
+            }
+        }
+        Ok(())
+    }
+
+    /// エンドポイントの `aliases` に別名を追加する
+    pub fn add_alias(&mut self, id: &str, alias: &str) -> Result<()> {
+        let block = self.block()?.with_context(|| format!("Endpoint '{id}' not found"))?;
+        let item = block.items[self.position(&block, id)?];
+        let (content_indent, first) = self.content_indent(item)?;
+        let scalar = format_scalar(alias, "");
+
+        let Some(line) = self.key_line(item, "aliases")? else {
+            self.lines.insert(first + 1, format!("{}aliases: [{scalar}]", " ".repeat(content_indent)));
+            return Ok(());
+        };
+        let (prefix, rest) = split_key(&self.lines[line], "aliases").context("Failed to locate aliases")?;
+        let (value, comment) = split_comment(rest);
+        let value = value.trim();
+
+        if let Some(inner) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            let inner = inner.trim();
+            let items = if inner.is_empty() { scalar } else { format!("{inner}, {scalar}") };
+            self.lines[line] = format!("{prefix}aliases: [{items}]{comment}");
+        } else if value.is_empty() {
+            // ブロック形式の一覧の最後に追加する
+            let mut last = line;
+            let mut indent = content_indent + DEFAULT_ITEM_INDENT;
+            for (i, text) in self.lines.iter().enumerate().take(item.end).skip(line + 1) {
+                let trimmed = text.trim_start();
+                if trimmed.starts_with('-') && leading_spaces(text) >= content_indent {
+                    last = i;
+                    indent = leading_spaces(text);
+                } else if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                    break;
+                }
+            }
+            self.lines.insert(last + 1, format!("{}- {scalar}", " ".repeat(indent)));
+        } else {
+            return Err(anyhow::anyhow!("Unsupported aliases value for endpoint '{id}'"));
+        }
+        Ok(())
+    }
+
+    // `mcp_endpoints:` のブロックを探す
+    fn block(&self) -> Result<Option<Block>> {
+        self.list_block("mcp_endpoints")
+    }
+
+    // トップレベルのブロック形式の一覧を探す
+    fn list_block(&self, name: &str) -> Result<Option<Block>> {
+        let Some(key) = self.key_position(name) else {
+            return Ok(None);
+        };
+        let (_, rest) = split_key(&self.lines[key], name).unwrap_or_default();
+        let value = split_comment(rest).0.trim();
+        if !value.is_empty() && value != "[]" {
+            return Err(anyhow::anyhow!("{name} must be a block list to be edited"));
+        }
+        let end = self.section_end(key);
+
+        let indent = self.lines[key + 1..end]
+            .iter()
+            .find(|line| line.trim_start().starts_with('-'))
+            .map_or(DEFAULT_ITEM_INDENT, |line| leading_spaces(line));
+        let starts: Vec<usize> = (key + 1..end)
+            .filter(|&i| leading_spaces(&self.lines[i]) == indent && self.lines[i].trim_start().starts_with('-'))
+            .collect();
+
+        let mut items: Vec<Item> = Vec::new();
+        for (n, &first) in starts.iter().enumerate() {
+            // 直前のコメント行を項目に含める
+            let floor = items.last().map_or(key + 1, |item| item.end);
+            let mut start = first;
+            while start > floor && self.lines[start - 1].trim_start().starts_with('#') {
+                start -= 1;
+            }
+            let limit = starts.get(n + 1).copied().unwrap_or(end);
+            let mut item_end = limit;
+            while item_end > first + 1 && self.lines[item_end - 1].trim().is_empty() {
+                item_end -= 1;
+            }
+            // 次の項目のコメントは次の項目に含める
+            while item_end > first + 1 && n + 1 < starts.len() && self.lines[item_end - 1].trim_start().starts_with('#') {
+                item_end -= 1;
+            }
+            while item_end > first + 1 && self.lines[item_end - 1].trim().is_empty() {
+                item_end -= 1;
+            }
+            items.push(Item { start, end: item_end });
+        }
+
+        Ok(Some(Block {
+            key,
+            end,
+            indent,
+            flow_empty: value == "[]",
+            items,
+        }))
+    }
+
+    // 追加できるブロック形式の一覧（キーが無い場合は追加し、`[]` はブロック形式にする）
+    fn editable_list(&mut self, name: &str) -> Result<Block> {
+        let block = if let Some(block) = self.list_block(name)? {
+            block
+        } else {
+            self.append_section(vec![format!("{name}:")]);
+            self.list_block(name)?.with_context(|| format!("Failed to add {name}"))?
+        };
+        if block.flow_empty {
+            self.lines[block.key] = format!("{name}:");
+        }
+        Ok(block)
+    }
+
+    fn append_section(&mut self, lines: Vec<String>) {
+        if self.lines.last().is_some_and(|l| !l.trim().is_empty()) {
+            self.lines.push(String::new());
+        }
+        self.lines.extend(lines);
+    }
+
+    // 現在の内容を YAML の値として解析する
+    fn value(&self) -> Result<Value> {
+        let text = self.text();
+        if text.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        serde_yaml::from_str(&text).with_context(|| format!("Failed to parse {}", self.path.display()))
+    }
+
+    // トップレベルのキーの行
+    fn key_position(&self, name: &str) -> Option<usize> {
+        self.lines.iter().position(|line| split_key(line, name).is_some_and(|(p, _)| p.is_empty()))
+    }
+
+    // トップレベルのキーの値の最後の内容行の次の行
+    fn section_end(&self, key: usize) -> usize {
+        let mut end = key + 1;
+        for (i, line) in self.lines.iter().enumerate().skip(key + 1) {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if leading_spaces(line) == 0 && !trimmed.starts_with('-') {
+                break;
+            }
+            end = i + 1;
+        }
+        end
+    }
+
+    // 項目ごとのID（解析した内容と項目の数が一致することを確認する）
+    fn ids(&self, block: &Block) -> Result<Vec<String>> {
+        let index = self.index()?;
+        if index.mcp_endpoints.len() != block.items.len() {
+            return Err(anyhow::anyhow!("Could not locate mcp_endpoints entries in {}", self.path.display()));
+        }
+        Ok(index.mcp_endpoints.into_iter().map(|e| e.id).collect())
+    }
+
+    fn position(&self, block: &Block, id: &str) -> Result<usize> {
+        self.ids(block)?
+            .iter()
+            .position(|i| i == id)
+            .with_context(|| format!("Endpoint '{id}' is not defined in agent_index.yml"))
+    }
+
+    // 項目のキーのインデントと `-` の行
+    fn content_indent(&self, item: Item) -> Result<(usize, usize)> {
+        let first = (item.start..item.end)
+            .find(|&i| self.lines[i].trim_start().starts_with('-'))
+            .context("Failed to locate endpoint entry")?;
+        let line = &self.lines[first];
+        let after_dash = &line[leading_spaces(line) + 1..];
+        Ok((leading_spaces(line) + 1 + leading_spaces(after_dash), first))
+    }
+
+    // 項目の直下のキーの行
+    fn key_line(&self, item: Item, key: &str) -> Result<Option<usize>> {
+        let (content_indent, first) = self.content_indent(item)?;
+        Ok((first..item.end).find(|&i| {
+            let line = &self.lines[i];
+            let body = if i == first { &line[content_indent.min(line.len())..] } else { line.as_str() };
+            (i == first || leading_spaces(line) == content_indent)
+                && split_key(body.trim_start(), key).is_some()
+        }))
+    }
+}
+
+/// `agent_index.yml` にエンドポイントを追加し、置き換えたエンドポイントのIDを返す
+///
+/// ファイルが無い場合は新しく作る。既存のエントリやコメントは保たれる
+pub fn upsert_endpoints(index_path: &Path, incoming: Vec<Value>) -> Result<Vec<String>> {
+    let mut document = IndexDocument::load(index_path)?;
+    let replaced = document.upsert_endpoints(incoming)?;
+    document.save()?;
+    Ok(replaced)
+}
+
+#[must_use] pub fn endpoint_value(endpoint: &McpEndpoint) -> Value {
+    let mut mapping = Mapping::new();
+    mapping.insert("id".into(), endpoint.id.clone().into());
+    if !endpoint.label.is_empty() {
+        mapping.insert("label".into(), endpoint.label.clone().into());
+    }
+    if !endpoint.description.is_empty() {
+        mapping.insert("description".into(), endpoint.description.clone().into());
+    }
+    mapping.insert("prompt_file".into(), endpoint.prompt_file.clone().into());
+    if let Some(trigger) = &endpoint.trigger {
+        mapping.insert("trigger".into(), trigger.clone().into());
+    }
+    if let Some(category) = &endpoint.category {
+        mapping.insert("category".into(), category.clone().into());
+    }
+    if !endpoint.tags.is_empty() {
+        mapping.insert("tags".into(), endpoint.tags.clone().into());
+    }
+    if !endpoint.arguments.is_empty() {
+        let arguments = endpoint
+            .arguments
+            .iter()
+            .map(|argument| {
+                let mut entry = Mapping::new();
+                entry.insert("name".into(), argument.name.clone().into());
+                if let Some(description) = &argument.description {
+                    entry.insert("description".into(), description.clone().into());
+                }
+                if argument.required {
+                    entry.insert("required".into(), true.into());
+                }
+                if let Some(default) = &argument.default {
+                    entry.insert("default".into(), default.clone().into());
+                }
+                Value::Mapping(entry)
+            })
+            .collect();
+        mapping.insert("arguments".into(), Value::Sequence(arguments));
+    }
+    if !endpoint.locales.is_empty() {
+        let locales = endpoint
+            .locales
+            .iter()
+            .map(|(locale, file)| (locale.clone().into(), file.clone().into()))
+            .collect();
+        mapping.insert("locales".into(), Value::Mapping(locales));
+    }
+    if !endpoint.attachments.is_empty() {
+        let attachments = endpoint
+            .attachments
+            .iter()
+            .map(|attachment| {
+                let details = [
+                    ("name", &attachment.name),
+                    ("description", &attachment.description),
+                    ("mime_type", &attachment.mime_type),
+                ];
+                if details.iter().all(|(_, value)| value.is_none()) {
+                    return attachment.path.clone().into();
+                }
+                let mut entry = Mapping::new();
+                entry.insert("path".into(), attachment.path.clone().into());
+                for (key, value) in details {
+                    if let Some(value) = value {
+                        entry.insert(key.into(), value.clone().into());
+                    }
+                }
+                Value::Mapping(entry)
+            })
+            .collect();
+        mapping.insert("attachments".into(), Value::Sequence(attachments));
+    }
+    if !endpoint.aliases.is_empty() {
+        mapping.insert("aliases".into(), endpoint.aliases.clone().into());
+    }
+    if let Some(deprecated) = &endpoint.deprecated {
+        let mut entry = Mapping::new();
+        if let Some(replaced_by) = &deprecated.replaced_by {
+            entry.insert("replaced_by".into(), replaced_by.clone().into());
+        }
+        if let Some(message) = &deprecated.message {
+            entry.insert("message".into(), message.clone().into());
+        }
+        mapping.insert("deprecated".into(), Value::Mapping(entry));
+    }
+    Value::Mapping(mapping)
+}
+
+// エントリのIDと別名
+fn endpoint_names(endpoint: &Value) -> BTreeSet<String> {
+    let id = endpoint.get("id").and_then(Value::as_str);
+    let aliases = endpoint
+        .get("aliases")
+        .and_then(Value::as_sequence)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    id.into_iter()
+        .chain(aliases.iter().filter_map(Value::as_str))
+        .map(ToString::to_string)
+        .collect()
+}
+
+// `incoming` の無い項目やキーを `current` に追加する（既存の値が優先される）
+fn merge_values(current: &Value, incoming: &Value) -> Value {
+    match (current, incoming) {
+        (Value::Sequence(items), Value::Sequence(new_items)) => {
+            let identity = |item: &Value| item.get("path").cloned().unwrap_or_else(|| item.clone());
+            let mut merged = items.clone();
+            for item in new_items {
+                if !merged.iter().any(|existing| identity(existing) == identity(item)) {
+                    merged.push(item.clone());
+                }
+            }
+            Value::Sequence(merged)
+        }
+        (Value::Mapping(entries), Value::Mapping(new_entries)) => {
+            let mut merged = entries.clone();
+            for (key, value) in new_entries {
+                let entry = match entries.get(key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => value.clone(),
+                };
+                merged.insert(key.clone(), entry);
+            }
+            Value::Mapping(merged)
+        }
+        _ => current.clone(),
+    }
+}
+
+// エントリを `indent` だけ字下げした `- ` で始まる行にする
+fn item_lines(value: &Value, indent: usize) -> Result<Vec<String>> {
+    let yaml = serde_yaml::to_string(&Value::Sequence(vec![value.clone()]))?;
+    Ok(yaml.lines().map(|line| format!("{}{line}", " ".repeat(indent))).collect())
+}
+
+// `key:` で始まる行を字下げ（`- ` を含む）と値に分ける
+fn split_key<'a>(line: &'a str, key: &str) -> Option<(&'a str, &'a str)> {
+    let trimmed = line.trim_start_matches([' ', '-']);
+    let prefix = &line[..line.len() - trimmed.len()];
+    let rest = trimmed
+        .strip_prefix(key)
+        .or_else(|| trimmed.strip_prefix(&format!("'{key}'")))
+        .or_else(|| trimmed.strip_prefix(&format!("\"{key}\"")))?
+        .strip_prefix(':')?;
+    (rest.is_empty() || rest.starts_with([' ', '\t'])).then_some((prefix, rest))
+}
+
+// 値と行末のコメント（前の空白を含む）に分ける
+fn split_comment(value: &str) -> (&str, &str) {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in value.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => {
+                let start = value[..i].trim_end().len();
+                return (&value[..start], &value[start..]);
+            }
+            None => {}
+        }
+        previous = c;
+    }
+    (value, "")
+}
+
+// 元の値の引用符の形式に合わせてスカラーを書く
+fn format_scalar(value: &str, original: &str) -> String {
+    if original.starts_with('\'') {
+        format!("'{}'", value.replace('\'', "''"))
+    } else if original.starts_with('"') {
+        serde_json::to_string(value).unwrap_or_default()
+    } else {
+        serde_yaml::to_string(value).map(|s| s.trim_end().to_string()).unwrap_or_default()
+    }
+}
+
+fn leading_spaces(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const INDEX: &str = "# Team library
+name: 'Team'
+
+mcp_endpoints:
+  # Reviews
+  - id: 'review'   # main entry
+    label: Review
+    prompt_file: prompts/review.md
+    aliases:
+      - code_review
+
+  - id: commit
+    prompt_file: commit.md
+
+# Lint settings
+lint:
+  rules:
+    prompt-size: error
+";
+
+    fn document(temp_dir: &TempDir, text: &str) -> IndexDocument {
+        let path = temp_dir.path().join("agent_index.yml");
+        fs::write(&path, text).unwrap();
+        IndexDocument::load(&path).unwrap()
+    }
+
+    fn ids(document: &IndexDocument) -> Vec<String> {
+        document.index().unwrap().mcp_endpoints.into_iter().map(|e| e.id).collect()
+    }
+
+    #[test]
+    fn test_edit_preserves_comments() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut document = document(&temp_dir, INDEX);
+
+        document.set_value("review", "id", "code-review").unwrap();
+        document.add_alias("code-review", "review").unwrap();
+        let endpoint = McpEndpoint {
+            id: "tests".to_string(),
+            prompt_file: "tests.md".to_string(),
+            ..Default::default()
+        };
+        document.insert_endpoint(&endpoint_value(&endpoint), Some("code-review")).unwrap();
+        document.reorder_endpoints(&["commit".to_string(), "code-review".to_string(), "tests".to_string()]).unwrap();
+
+        assert_eq!(
+            document.text(),
+            "# Team library
+name: 'Team'
+
+mcp_endpoints:
+  - id: commit
+    prompt_file: commit.md
+
+  # Reviews
+  - id: 'code-review'   # main entry
+    label: Review
+    prompt_file: prompts/review.md
+    aliases:
+      - code_review
+      - review
+
+  - id: tests
+    prompt_file: tests.md
+
+# Lint settings
+lint:
+  rules:
+    prompt-size: error
+"
+        );
+
+        document.remove_endpoint("tests").unwrap();
+        document.remove_endpoint("commit").unwrap();
+        assert_eq!(ids(&document), vec!["code-review"]);
+        assert!(document.text().contains("  # Reviews\n  - id: 'code-review'   # main entry\n"));
+        assert!(document.text().ends_with("      - review\n\n# Lint settings\nlint:\n  rules:\n    prompt-size: error\n"));
+    }
+
+    #[test]
+    fn test_insert_into_empty_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let endpoint = McpEndpoint {
+            id: "review".to_string(),
+            prompt_file: "review.md".to_string(),
+            ..Default::default()
+        };
+
+        let mut document = document(&temp_dir, "name: Empty # no prompts yet\nmcp_endpoints: []\n");
+        document.insert_endpoint(&endpoint_value(&endpoint), None).unwrap();
+        assert_eq!(document.text(), "name: Empty # no prompts yet\nmcp_endpoints:\n  - id: review\n    prompt_file: review.md\n");
+
+        let mut document = IndexDocument::load(&temp_dir.path().join("missing.yml")).unwrap();
+        document.insert_endpoint(&endpoint_value(&endpoint), None).unwrap();
+        assert_eq!(ids(&document), vec!["review"]);
+
+        let mut document = self::document(&temp_dir, "mcp_endpoints: [{id: a, prompt_file: a.md}]\n");
+        assert!(document.insert_endpoint(&endpoint_value(&endpoint), None).is_err());
+    }
+}
@@ -0,0 +1,288 @@
+use super::{display_path, sources, Finding, LintConfig, LintRule, RuleLevel};
+use crate::agent_library::template;
+use crate::agent_library::types::AgentLibrary;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+// API キーやトークンと思われる文字列
+static SECRET_PATTERNS: LazyLock<Vec<(&str, Regex)>> = LazyLock::new(|| {
+    [
+        ("API key", r"\bsk-[A-Za-z0-9_-]{20,}"),
+        ("GitHub token", r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})"),
+        ("Slack token", r"\bxox[abprs]-[A-Za-z0-9-]{10,}"),
+        ("AWS access key", r"\bAKIA[0-9A-Z]{16}\b"),
+        ("Google API key", r"\bAIza[0-9A-Za-z_-]{35}"),
+        ("private key", r"-----BEGIN [A-Z ]*PRIVATE KEY-----"),
+        (
+            "credential",
+            r#"(?i)\b(?:api[_-]?key|secret|token|password)\s*[:=]\s*["']?[A-Za-z0-9/+_-]{16,}"#,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(name, pattern)| Regex::new(pattern).ok().map(|regex| (name, regex)))
+    .collect()
+});
+
+/// 既定で有効なルール
+#[must_use] pub fn default_rules() -> Vec<Box<dyn LintRule>> {
+    vec![
+        Box::new(UndeclaredPlaceholder),
+        Box::new(UnusedArgument),
+        Box::new(EmptyDescription),
+        Box::new(DuplicateDescription),
+        Box::new(PromptSize),
+        Box::new(TemplateSyntax),
+        Box::new(Secret),
+    ]
+}
+
+struct UndeclaredPlaceholder;
+
+impl LintRule for UndeclaredPlaceholder {
+    fn id(&self) -> &'static str {
+        "undeclared-placeholder"
+    }
+
+    fn description(&self) -> &'static str {
+        "Placeholders used in the body must be declared as arguments"
+    }
+
+    fn check(&self, library: &AgentLibrary, _config: &LintConfig) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        for prompt in &library.prompts {
+            let mut declared: HashSet<&str> = prompt.arguments.iter().map(|a| a.name.as_str()).collect();
+            if !prompt.variants.is_empty() {
+                declared.insert("locale");
+            }
+
+            let mut reported = HashSet::new();
+            for source in sources(prompt) {
+                for placeholder in template::placeholders(source.content) {
+                    if !declared.contains(placeholder.name) && reported.insert(placeholder.name) {
+                        findings.push(Finding::in_source(
+                            prompt,
+                            &source,
+                            placeholder.text,
+                            format!("Placeholder '{}' is not declared as an argument", placeholder.name),
+                        ));
+                    }
+                }
+            }
+        }
+
+        findings
+    }
+}
+
+struct UnusedArgument;
+
+impl LintRule for UnusedArgument {
+    fn id(&self) -> &'static str {
+        "unused-argument"
+    }
+
+    fn description(&self) -> &'static str {
+        "Declared arguments should be used in the body"
+    }
+
+    fn check(&self, library: &AgentLibrary, _config: &LintConfig) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        for prompt in &library.prompts {
+            let used: HashSet<&str> = sources(prompt)
+                .iter()
+                .flat_map(|source| template::placeholders(source.content))
+                .map(|p| p.name)
+                .collect();
+            for argument in &prompt.arguments {
+                if !used.contains(argument.name.as_str()) {
+                    findings.push(Finding::for_prompt(
+                        prompt,
+                        None,
+                        format!("Argument '{}' is never used", argument.name),
+                    ));
+                }
+            }
+        }
+
+        findings
+    }
+}
+
+struct EmptyDescription;
+
+impl LintRule for EmptyDescription {
+    fn id(&self) -> &'static str {
+        "empty-description"
+    }
+
+    fn description(&self) -> &'static str {
+        "Prompts should have a description"
+    }
+
+    fn check(&self, library: &AgentLibrary, _config: &LintConfig) -> Vec<Finding> {
+        library
+            .prompts
+            .iter()
+            .filter(|p| p.description.trim().is_empty())
+            .map(|p| Finding::for_prompt(p, None, "Description is empty".to_string()))
+            .collect()
+    }
+}
+
+struct DuplicateDescription;
+
+impl LintRule for DuplicateDescription {
+    fn id(&self) -> &'static str {
+        "duplicate-description"
+    }
+
+    fn description(&self) -> &'static str {
+        "Descriptions should distinguish prompts from each other"
+    }
+
+    fn check(&self, library: &AgentLibrary, _config: &LintConfig) -> Vec<Finding> {
+        let mut first_by_description: HashMap<&str, &str> = HashMap::new();
+        let mut findings = Vec::new();
+
+        for prompt in &library.prompts {
+            let description = prompt.description.trim();
+            if description.is_empty() {
+                continue;
+            }
+            match first_by_description.get(description) {
+                Some(first) => findings.push(Finding::for_prompt(
+                    prompt,
+                    None,
+                    format!("Description is the same as prompt '{first}'"),
+                )),
+                None => {
+                    first_by_description.insert(description, &prompt.id);
+                }
+            }
+        }
+
+        findings
+    }
+}
+
+struct PromptSize;
+
+impl LintRule for PromptSize {
+    fn id(&self) -> &'static str {
+        "prompt-size"
+    }
+
+    fn description(&self) -> &'static str {
+        "Prompts should stay within the size budget (lint.max_prompt_chars)"
+    }
+
+    fn check(&self, library: &AgentLibrary, config: &LintConfig) -> Vec<Finding> {
+        let max = config.max_prompt_chars();
+        let mut findings = Vec::new();
+
+        for prompt in &library.prompts {
+            let chars = prompt.content.chars().count();
+            if chars > max {
+                findings.push(Finding::for_prompt(
+                    prompt,
+                    None,
+                    format!("Prompt is {chars} characters long (limit {max})"),
+                ));
+            }
+            for variant in &prompt.variants {
+                let chars = variant.content.chars().count();
+                if chars > max {
+                    findings.push(Finding {
+                        prompt_id: Some(prompt.id.clone()),
+                        file: Some(variant.file_path.clone()),
+                        position: None,
+                        message: format!(
+                            "{} is {chars} characters long (limit {max})",
+                            display_path(library, &variant.file_path)
+                        ),
+                    });
+                }
+            }
+        }
+
+        findings
+    }
+}
+
+struct TemplateSyntax;
+
+impl LintRule for TemplateSyntax {
+    fn id(&self) -> &'static str {
+        "template-syntax"
+    }
+
+    fn description(&self) -> &'static str {
+        "Template placeholders must be closed and well-formed"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Error
+    }
+
+    fn check(&self, library: &AgentLibrary, _config: &LintConfig) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        for prompt in &library.prompts {
+            for source in sources(prompt) {
+                for error in template::syntax_errors(source.content) {
+                    findings.push(Finding::in_source(prompt, &source, error.text, error.message));
+                }
+            }
+        }
+
+        findings
+    }
+}
+
+struct Secret;
+
+impl LintRule for Secret {
+    fn id(&self) -> &'static str {
+        "secret"
+    }
+
+    fn description(&self) -> &'static str {
+        "API keys, tokens and private keys must not be pasted into prompts"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Error
+    }
+
+    fn check(&self, library: &AgentLibrary, _config: &LintConfig) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        for prompt in &library.prompts {
+            for source in sources(prompt) {
+                // 複数のパターンに一致した箇所は最初のパターンでのみ報告する
+                let mut reported: Vec<std::ops::Range<usize>> = Vec::new();
+                for (name, pattern) in SECRET_PATTERNS.iter() {
+                    for found in pattern.find_iter(source.content) {
+                        if reported.iter().any(|r| r.start < found.end() && found.start() < r.end) {
+                            continue;
+                        }
+                        reported.push(found.range());
+                        // 診断に値そのものを残さない
+                        let masked: String = found.as_str().chars().take(6).collect();
+                        findings.push(Finding::in_source(
+                            prompt,
+                            &source,
+                            found.as_str(),
+                            format!("Possible {name} found ({masked}…)"),
+                        ));
+                    }
+                }
+            }
+        }
+
+        findings
+    }
+}
@@ -0,0 +1,185 @@
+use super::types::{McpEndpoint, PromptVariant};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// ISO 639-1 の言語コード（ファイル名からロケールを自動検出する場合に使う）
+const LANGUAGE_CODES: [&str; 184] = [
+    "aa", "ab", "ae", "af", "ak", "am", "an", "ar", "as", "av", "ay", "az", "ba", "be", "bg", "bh", "bi", "bm", "bn",
+    "bo", "br", "bs", "ca", "ce", "ch", "co", "cr", "cs", "cu", "cv", "cy", "da", "de", "dv", "dz", "ee", "el", "en",
+    "eo", "es", "et", "eu", "fa", "ff", "fi", "fj", "fo", "fr", "fy", "ga", "gd", "gl", "gn", "gu", "gv", "ha", "he",
+    "hi", "ho", "hr", "ht", "hu", "hy", "hz", "ia", "id", "ie", "ig", "ii", "ik", "io", "is", "it", "iu", "ja", "jv",
+    "ka", "kg", "ki", "kj", "kk", "kl", "km", "kn", "ko", "kr", "ks", "ku", "kv", "kw", "ky", "la", "lb", "lg", "li",
+    "ln", "lo", "lt", "lu", "lv", "mg", "mh", "mi", "mk", "ml", "mn", "mr", "ms", "mt", "my", "na", "nb", "nd", "ne",
+    "ng", "nl", "nn", "no", "nr", "nv", "ny", "oc", "oj", "om", "or", "os", "pa", "pi", "pl", "ps", "pt", "qu", "rm",
+    "rn", "ro", "ru", "rw", "sa", "sc", "sd", "se", "sg", "si", "sk", "sl", "sm", "sn", "so", "sq", "sr", "ss", "st",
+    "su", "sv", "sw", "ta", "te", "tg", "th", "ti", "tk", "tl", "tn", "to", "tr", "ts", "tt", "tw", "ty", "ug", "uk",
+    "ur", "uz", "ve", "vi", "vo", "wa", "wo", "xh", "yi", "yo", "za", "zh", "zu",
+];
+
+/// `ja`, `en-US`, `zh_Hant` のようなロケールタグか判定する
+#[must_use] pub fn is_locale_tag(tag: &str) -> bool {
+    let mut subtags = tag.split(['-', '_']);
+    let Some(language) = subtags.next() else {
+        return false;
+    };
+    (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags.all(|s| (2..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// ファイル名から自動検出するロケールか判定する
+///
+/// ISO 639-1 の言語コードに、任意で `Hant` のような文字体系と `BR` / `419` のような地域が続くものだけを対象にする
+/// （`setup.git.md` や `review.old.md` をロケール別ファイルとして扱わないため）。それ以外は `locales:` で指定する
+#[must_use] pub fn is_detected_locale(tag: &str) -> bool {
+    let mut subtags = tag.split(['-', '_']).peekable();
+    if !subtags.next().is_some_and(|language| LANGUAGE_CODES.contains(&language)) {
+        return false;
+    }
+    let is_script = |s: &str| {
+        s.len() == 4 && s.starts_with(|c: char| c.is_ascii_uppercase()) && s[1..].chars().all(|c| c.is_ascii_lowercase())
+    };
+    if subtags.peek().is_some_and(|s| is_script(s)) {
+        subtags.next();
+    }
+    let is_region = |s: &str| {
+        (s.len() == 2 && s.chars().all(|c| c.is_ascii_uppercase())) || (s.len() == 3 && s.chars().all(|c| c.is_ascii_digit()))
+    };
+    if subtags.peek().is_some_and(|s| is_region(s)) {
+        subtags.next();
+    }
+    subtags.next().is_none()
+}
+
+/// `review.ja.md` を基本ファイル名 `review.md` とロケール `ja` に分ける（ロケールは `is_detected_locale` で判定する）
+#[must_use] pub fn split_variant(file_name: &str) -> Option<(String, String)> {
+    let stem = file_name.strip_suffix(".md")?;
+    let (base, locale) = stem.rsplit_once('.')?;
+    if base.is_empty() || !is_detected_locale(locale) {
+        return None;
+    }
+    Some((format!("{base}.md"), locale.to_string()))
+}
+
+/// エンドポイントのロケール別ファイルを `(ロケール, パス)` の一覧で返す
+///
+/// `locales:` で明示したファイルと、プロンプトファイルと同じディレクトリの
+/// `<名前>.<ロケール>.md` を対象にする。同じロケールは `locales:` の指定が優先される
+#[must_use] pub fn variant_files(agent_lib_path: &Path, endpoint: &McpEndpoint) -> Vec<(String, PathBuf)> {
+    let mut variants: Vec<(String, PathBuf)> = endpoint
+        .locales
+        .iter()
+        .map(|(locale, file)| (locale.clone(), agent_lib_path.join(file)))
+        .collect();
+
+    let prompt_path = agent_lib_path.join(&endpoint.prompt_file);
+    let (Some(directory), Some(prompt_name)) = (prompt_path.parent(), prompt_path.file_name()) else {
+        return variants;
+    };
+    let Ok(entries) = fs::read_dir(directory) else {
+        return variants;
+    };
+
+    for entry in entries.filter_map(std::result::Result::ok) {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some((base, locale)) = split_variant(&file_name) else {
+            continue;
+        };
+        if prompt_name == base.as_str() && !variants.iter().any(|(l, _)| same_locale(l, &locale)) {
+            variants.push((locale, entry.path()));
+        }
+    }
+
+    variants.sort_by(|a, b| a.0.cmp(&b.0));
+    variants
+}
+
+/// 要求されたロケールに最も近いバリアントを選ぶ
+///
+/// 完全一致、言語部分の一致（`ja-JP` に対する `ja`、`en` に対する `en-US`）の順に探す
+#[must_use] pub fn select<'a>(variants: &'a [PromptVariant], locale: &str) -> Option<&'a PromptVariant> {
+    let language = primary_language(locale);
+    variants
+        .iter()
+        .find(|v| same_locale(&v.locale, locale))
+        .or_else(|| variants.iter().find(|v| same_locale(&v.locale, &language)))
+        .or_else(|| variants.iter().find(|v| primary_language(&v.locale) == language))
+}
+
+fn same_locale(a: &str, b: &str) -> bool {
+    a.replace('_', "-").eq_ignore_ascii_case(&b.replace('_', "-"))
+}
+
+fn primary_language(locale: &str) -> String {
+    locale.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    fn variant(locale: &str) -> PromptVariant {
+        PromptVariant {
+            locale: locale.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_split_variant() {
+        assert_eq!(split_variant("review.ja.md"), Some(("review.md".to_string(), "ja".to_string())));
+        assert_eq!(split_variant("review.pt-BR.md"), Some(("review.md".to_string(), "pt-BR".to_string())));
+        assert_eq!(split_variant("review.md"), None);
+        assert_eq!(split_variant("release.notes.md"), None);
+        assert_eq!(split_variant(".ja.md"), None);
+        assert_eq!(split_variant("review.zh_Hant.md"), Some(("review.md".to_string(), "zh_Hant".to_string())));
+        assert_eq!(split_variant("review.es-419.md"), Some(("review.md".to_string(), "es-419".to_string())));
+
+        // 言語コードではない短い接尾辞はロケールとして扱わない
+        for name in ["setup.git.md", "review.old.md", "notes.tmp.md", "review.xx.md", "review.ja-jp.md", "review.JA.md"] {
+            assert_eq!(split_variant(name), None, "{name}");
+        }
+        assert!(is_locale_tag("old"));
+        assert!(!is_detected_locale("old"));
+    }
+
+    #[test]
+    fn test_variant_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("prompts")).unwrap();
+        for name in ["review.md", "review.ja.md", "review.en.md", "other.fr.md"] {
+            fs::write(root.join("prompts").join(name), "# Review").unwrap();
+        }
+        fs::write(root.join("review-english.md"), "# Review").unwrap();
+
+        let endpoint = McpEndpoint {
+            id: "review".to_string(),
+            prompt_file: "prompts/review.md".to_string(),
+            locales: BTreeMap::from([("en".to_string(), "review-english.md".to_string())]),
+            ..Default::default()
+        };
+
+        let variants = variant_files(root, &endpoint);
+        assert_eq!(
+            variants,
+            vec![
+                ("en".to_string(), root.join("review-english.md")),
+                ("ja".to_string(), root.join("prompts/review.ja.md")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_variant() {
+        let variants = vec![variant("en-US"), variant("ja"), variant("pt_BR")];
+
+        assert_eq!(select(&variants, "ja").unwrap().locale, "ja");
+        assert_eq!(select(&variants, "ja-JP").unwrap().locale, "ja");
+        assert_eq!(select(&variants, "en").unwrap().locale, "en-US");
+        assert_eq!(select(&variants, "pt-br").unwrap().locale, "pt_BR");
+        assert!(select(&variants, "fr").is_none());
+    }
+}
@@ -0,0 +1,217 @@
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag};
+use serde::{Deserialize, Serialize};
+
+/// プロンプト本文の構造の要約（目次表示やリンク検証に使う）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarkdownSummary {
+    pub headings: Vec<Heading>,
+    pub code_blocks: Vec<CodeBlock>,
+    pub links: Vec<Link>,
+    // 英数字の連続を1語、かな・漢字・ハングルは1文字を1語として数える
+    pub word_count: usize,
+    // 空白を除いたテキストの文字数（記法は含まない）
+    pub char_count: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Heading {
+    pub level: usize,
+    pub text: String,
+    // 1始まりの行番号
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    pub line: usize,
+    pub line_count: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link {
+    pub url: String,
+    pub text: String,
+    pub image: bool,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Markdown を解析して見出し、コードブロック、リンク、語数を集計する
+#[must_use] pub fn summarize(content: &str) -> MarkdownSummary {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let position = |offset: usize| {
+        let line = line_starts.partition_point(|&start| start <= offset);
+        (line, offset - line_starts[line - 1] + 1)
+    };
+
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+
+    let mut summary = MarkdownSummary::default();
+    let mut heading: Option<Heading> = None;
+    let mut code_block: Option<CodeBlock> = None;
+    // 画像を含むリンクのように入れ子になる場合がある
+    let mut links: Vec<Link> = Vec::new();
+
+    for (event, range) in Parser::new_ext(content, options).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading(level, ..)) => {
+                heading = Some(Heading {
+                    level: level as usize,
+                    text: String::new(),
+                    line: position(range.start).0,
+                });
+            }
+            Event::End(Tag::Heading(..)) => {
+                if let Some(mut heading) = heading.take() {
+                    heading.text = heading.text.trim().to_string();
+                    summary.headings.push(heading);
+                }
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().map(ToString::to_string),
+                    CodeBlockKind::Indented => None,
+                };
+                code_block = Some(CodeBlock {
+                    language,
+                    line: position(range.start).0,
+                    line_count: 0,
+                });
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                if let Some(code_block) = code_block.take() {
+                    summary.code_blocks.push(code_block);
+                }
+            }
+            Event::Start(Tag::Link(_, url, _)) => {
+                let (line, column) = position(range.start);
+                links.push(Link { url: url.to_string(), line, column, ..Default::default() });
+            }
+            Event::Start(Tag::Image(_, url, _)) => {
+                let (line, column) = position(range.start);
+                links.push(Link { url: url.to_string(), image: true, line, column, ..Default::default() });
+            }
+            Event::End(Tag::Link(..) | Tag::Image(..)) => {
+                if let Some(mut link) = links.pop() {
+                    link.text = link.text.trim().to_string();
+                    summary.links.push(link);
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(code_block) = code_block.as_mut() {
+                    code_block.line_count += text.lines().count();
+                }
+                if let Some(heading) = heading.as_mut() {
+                    heading.text.push_str(&text);
+                }
+                for link in &mut links {
+                    link.text.push_str(&text);
+                }
+                summary.word_count += count_words(&text);
+                summary.char_count += text.chars().filter(|c| !c.is_whitespace()).count();
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some(heading) = heading.as_mut() {
+                    heading.text.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 出現順に並べる（入れ子のリンクは閉じた順に追加されるため）
+    summary.links.sort_by_key(|link| (link.line, link.column));
+    summary
+}
+
+/// 相対リンクの場合はリンク先のパス部分を返す
+///
+/// URL スキーム付き、絶対パス、ページ内リンク、テンプレート変数を含むリンクは対象外
+#[must_use] pub fn relative_link_target(url: &str) -> Option<&str> {
+    let path = url.split(['#', '?']).next().unwrap_or_default();
+    let has_scheme = path
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.is_empty() && !scheme.contains('/'));
+    if path.is_empty() || path.starts_with('/') || has_scheme || path.contains("{{") {
+        return None;
+    }
+    Some(path)
+}
+
+fn count_words(text: &str) -> usize {
+    let mut count = 0;
+    let mut in_word = false;
+
+    for c in text.chars() {
+        if is_cjk(c) {
+            count += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() || c == '_' {
+            if !in_word {
+                count += 1;
+                in_word = true;
+            }
+        } else if !(in_word && (c == '\'' || c == '-')) {
+            in_word = false;
+        }
+    }
+
+    count
+}
+
+const fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3040}'..='\u{30FF}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{AC00}'..='\u{D7AF}' | '\u{F900}'..='\u{FAFF}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_structure() {
+        let content = "# Code Review\n\nCheck the [guide](docs/guide.md#rules) and ![diagram](img/flow.png).\n\n## Steps\n\n```rust\nfn main() {}\n```\n\n    indented\n";
+        let summary = summarize(content);
+
+        let headings: Vec<_> = summary.headings.iter().map(|h| (h.level, h.text.as_str(), h.line)).collect();
+        assert_eq!(headings, vec![(1, "Code Review", 1), (2, "Steps", 5)]);
+
+        assert_eq!(summary.code_blocks.len(), 2);
+        assert_eq!(summary.code_blocks[0].language.as_deref(), Some("rust"));
+        assert_eq!(summary.code_blocks[0].line, 7);
+        assert_eq!(summary.code_blocks[0].line_count, 1);
+        assert_eq!(summary.code_blocks[1].language, None);
+
+        assert_eq!(summary.links.len(), 2);
+        assert_eq!(summary.links[0].url, "docs/guide.md#rules");
+        assert_eq!(summary.links[0].text, "guide");
+        assert_eq!((summary.links[0].line, summary.links[0].column), (3, 11));
+        assert!(summary.links[1].image);
+    }
+
+    #[test]
+    fn test_count_words() {
+        let summary = summarize("Don't review well-known code.\n\nコードをレビュー");
+        assert_eq!(summary.word_count, 4 + 8);
+        assert_eq!(summary.char_count, "Don'treviewwell-knowncode.コードをレビュー".chars().count());
+    }
+
+    #[test]
+    fn test_relative_link_target() {
+        assert_eq!(relative_link_target("docs/guide.md#rules"), Some("docs/guide.md"));
+        assert_eq!(relative_link_target("../README.md?plain=1"), Some("../README.md"));
+        assert_eq!(relative_link_target("https://example.com"), None);
+        assert_eq!(relative_link_target("mailto:team@example.com"), None);
+        assert_eq!(relative_link_target("#steps"), None);
+        assert_eq!(relative_link_target("/etc/hosts"), None);
+        assert_eq!(relative_link_target("{{base_url}}/guide.md"), None);
+    }
+}
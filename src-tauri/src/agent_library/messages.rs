@@ -0,0 +1,112 @@
+use super::types::PromptMessage;
+use anyhow::Result;
+
+// MCP のプロンプトメッセージで使えるロール
+const ROLES: &[&str] = &["user", "assistant"];
+
+/// `<!-- role: assistant -->` の区切りで本文をメッセージに分割する
+///
+/// 最初の区切りより前の本文は `user` メッセージになる。
+/// 区切りがない場合は本文全体を1つの `user` メッセージとして返すので、結果は必ず1つ以上になる。
+/// 空のセクションは除くが、末尾の空の `assistant` セクションはプレフィルとして残す
+pub fn split_messages(content: &str) -> Result<Vec<PromptMessage>> {
+    let mut messages = Vec::new();
+    let mut role = "user".to_string();
+    let mut rest = content;
+
+    while let Some((start, end, marker_role)) = find_role_marker(rest)? {
+        push_message(&mut messages, &role, &rest[..start], false);
+        role = marker_role;
+        rest = &rest[end..];
+    }
+    push_message(&mut messages, &role, rest, role == "assistant");
+
+    if messages.is_empty() {
+        messages.push(PromptMessage {
+            role: "user".to_string(),
+            content: String::new(),
+        });
+    }
+
+    Ok(messages)
+}
+
+// 次のロール区切りの位置（開始, 終了）とロールを返す
+fn find_role_marker(content: &str) -> Result<Option<(usize, usize, String)>> {
+    let mut offset = 0;
+
+    while let Some(start) = content[offset..].find("<!--") {
+        let start = offset + start;
+        let Some(length) = content[start..].find("-->") else {
+            return Ok(None);
+        };
+        let end = start + length + "-->".len();
+        let comment = content[start + "<!--".len()..start + length].trim();
+
+        if let Some(role) = comment.strip_prefix("role:") {
+            let role = role.trim().to_lowercase();
+            if !ROLES.contains(&role.as_str()) {
+                return Err(anyhow::anyhow!(
+                    "Unknown message role '{role}' (expected one of: {})",
+                    ROLES.join(", ")
+                ));
+            }
+            return Ok(Some((start, end, role)));
+        }
+
+        offset = end;
+    }
+
+    Ok(None)
+}
+
+fn push_message(messages: &mut Vec<PromptMessage>, role: &str, content: &str, keep_empty: bool) {
+    let content = content.trim_matches(['\r', '\n']);
+    if content.trim().is_empty() && !keep_empty {
+        return;
+    }
+    let content = if content.trim().is_empty() { "" } else { content };
+    messages.push(PromptMessage {
+        role: role.to_string(),
+        content: content.to_string(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_without_markers() {
+        let messages = split_messages("# Review\n\n<!-- note -->\nCheck the code.").unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, "user");
+        assert_eq!(messages[0].content, "# Review\n\n<!-- note -->\nCheck the code.");
+    }
+
+    #[test]
+    fn test_split_role_sections() {
+        let content = "Write a commit message.\n\n<!-- role: assistant -->\nfeat: add parser\n\n<!-- role: user -->\nNow for {{diff}}\n<!-- role: Assistant -->\n";
+        let messages = split_messages(content).unwrap();
+
+        // 末尾の空の assistant セクションはプレフィルとして残る
+        let roles: Vec<_> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["user", "assistant", "user", "assistant"]);
+        assert_eq!(messages[0].content, "Write a commit message.");
+        assert_eq!(messages[1].content, "feat: add parser");
+        assert_eq!(messages[2].content, "Now for {{diff}}");
+        assert_eq!(messages[3].content, "");
+
+        // 途中の空のセクションは除く
+        let messages = split_messages("<!-- role: assistant -->\n\n<!-- role: user -->\nHello").unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, "user");
+        assert_eq!(split_messages("").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_split_unknown_role() {
+        let error = split_messages("<!-- role: system -->\nYou are a bot").unwrap_err();
+        assert!(error.to_string().contains("Unknown message role 'system'"));
+    }
+}
@@ -1,5 +1,29 @@
+pub mod bundle;
+pub mod cache;
+pub mod discovery;
+pub mod endpoints;
+pub mod exporter;
+pub mod front_matter;
+pub mod history;
+pub mod include;
+pub mod importer;
+pub mod index_file;
+pub mod lint;
+pub mod locale;
+pub mod markdown;
+pub mod messages;
 pub mod parser;
+pub mod resources;
+pub mod scaffold;
+pub mod search;
+pub mod storage;
+pub mod taxonomy;
+pub mod template;
 pub mod types;
+pub mod validator;
 
 pub use parser::*;
-pub use types::*;
\ No newline at end of file
+pub use search::{RepositoryInfo, SearchOptions};
+pub use taxonomy::{PromptQuery, Taxonomy};
+pub use types::*;
+pub use validator::{Diagnostic, Severity};
\ No newline at end of file
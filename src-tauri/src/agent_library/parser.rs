@@ -1,18 +1,34 @@
-use super::types::{AgentLibrary, AgentIndex, Prompt};
+use super::{cache, discovery, front_matter, include, lint, locale, markdown, messages, resources, storage, template};
+use super::taxonomy::Taxonomy;
+use super::search::{self, SearchOptions};
+use super::front_matter::FrontMatter;
+use super::markdown::MarkdownSummary;
+use super::types::{AgentLibrary, AgentIndex, McpEndpoint, Prompt, PromptMessage, PromptVariant};
+use super::validator::{self, Diagnostic};
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
-use walkdir::WalkDir;
-use std::collections::HashMap;
-use std::sync::Mutex;
-// use once_cell::sync::Lazy; // 現在未使用
-
-// Agent Libraryキャッシュの実装（シンプルなHashMapベース）
-type CacheEntry = (AgentLibrary, SystemTime);
-static AGENT_LIBRARY_CACHE: std::sync::LazyLock<Mutex<HashMap<PathBuf, CacheEntry>>> = std::sync::LazyLock::new(|| {
-    Mutex::new(HashMap::new())
-});
+
+/// `AgentLibraryParser::apply_change` の結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LibraryUpdate {
+    // ライブラリに関係しないファイル
+    Unchanged,
+    // 読み直した（または削除した）プロンプトのID
+    Prompts(Vec<String>),
+    // ライブラリ全体を読み直した
+    Reloaded,
+}
+
+// 読み込んだプロンプトファイル
+struct PromptFile {
+    front_matter: Option<FrontMatter>,
+    content: String,
+    raw_content: String,
+    included_files: Vec<PathBuf>,
+    messages: Vec<PromptMessage>,
+    summary: MarkdownSummary,
+}
 
 pub struct AgentLibraryParser;
 
@@ -24,39 +40,164 @@ impl AgentLibraryParser {
             return Err(anyhow::anyhow!(".agent_library directory not found in {}", repo_path.display()));
         }
 
-        // ディレクトリの最終更新時間を確認
-        let dir_modified = fs::metadata(&agent_lib_path)
-            .and_then(|m| m.modified())
-            .unwrap_or(SystemTime::UNIX_EPOCH);
-
-        // キャッシュから確認
-        if let Ok(cache) = AGENT_LIBRARY_CACHE.lock() {
-            if let Some((cached_library, cached_time)) = cache.get(&agent_lib_path) {
-                if *cached_time >= dir_modified {
-                    tracing::debug!("Serving agent library from cache: {}", agent_lib_path.display());
-                    return Ok(cached_library.clone());
-                }
-            }
+        // キャッシュから確認（参照しているファイルの指紋が変わっていなければ再利用）
+        if let Some(cached_library) = cache::lookup(&agent_lib_path) {
+            tracing::debug!("Serving agent library from cache: {}", agent_lib_path.display());
+            return Ok(cached_library);
         }
 
         tracing::debug!("Cache miss, parsing agent library: {}", agent_lib_path.display());
-        let index = Self::parse_index(&agent_lib_path)?;
-        let prompts = Self::parse_prompts(&agent_lib_path, &index)?;
+        let mut roots = Vec::new();
+        let library = Self::load_library(&agent_lib_path, &mut Vec::new(), &mut roots)?;
 
-        let library = AgentLibrary {
+        // キャッシュに保存
+        cache::store(&agent_lib_path, &library, &roots);
+
+        Ok(library)
+    }
+
+    /// 指定したパス（リポジトリ、`.agent_library`、またはその中のファイル）に関係するキャッシュを破棄する
+    pub fn invalidate(path: &Path) {
+        cache::invalidate(path);
+    }
+
+    /// キャッシュをすべて破棄する
+    pub fn clear() {
+        cache::clear();
+    }
+
+    // ライブラリを読み込み、extends があれば継承元を先に読み込んでマージする
+    fn load_library(agent_lib_path: &Path, chain: &mut Vec<PathBuf>, roots: &mut Vec<PathBuf>) -> Result<AgentLibrary> {
+        let canonical = agent_lib_path
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {}", agent_lib_path.display()))?;
+
+        if let Some(position) = chain.iter().position(|p| *p == canonical) {
+            let cycle = chain[position..]
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(anyhow::anyhow!("Library extension cycle detected: {cycle}"));
+        }
+        chain.push(canonical.clone());
+        roots.push(canonical);
+
+        let mut index = Self::parse_index(agent_lib_path)?;
+        // include パターンに一致するファイルを明示エントリの後ろに追加
+        let discovered = discovery::discover_endpoints(agent_lib_path, &index)
+            .with_context(|| format!("Failed to discover prompts in {}", agent_lib_path.display()))?;
+        index.mcp_endpoints.extend(discovered);
+        let prompts = Self::parse_prompts(agent_lib_path, &index)?;
+        let resources = resources::resolve(agent_lib_path, &index.resources, None);
+
+        let mut library = AgentLibrary {
             index,
-            base_path: agent_lib_path.clone(),
+            base_path: agent_lib_path.to_path_buf(),
             prompts,
+            resources,
+            taxonomy: Taxonomy::default(),
         };
 
-        // キャッシュに保存
-        if let Ok(mut cache) = AGENT_LIBRARY_CACHE.lock() {
-            cache.insert(agent_lib_path, (library.clone(), dir_modified));
+        if let Some(extends) = library.index.extends.clone() {
+            let base_path = Self::resolve_extends(&extends, agent_lib_path)?;
+            let base = Self::load_library(&base_path, chain, roots)
+                .with_context(|| format!("Failed to load base library '{extends}'"))?;
+            library = Self::merge_libraries(base, library);
         }
+        library.taxonomy = Taxonomy::build(&library.prompts);
 
+        chain.pop();
         Ok(library)
     }
 
+    /// extends の値を `.agent_library` ディレクトリのパスに解決する
+    ///
+    /// 相対パスはリポジトリ（`.agent_library` の親ディレクトリ）からの相対パス。
+    /// リポジトリのパスを指定した場合はその中の `.agent_library` を使う
+    pub fn resolve_extends(extends: &str, agent_lib_path: &Path) -> Result<PathBuf> {
+        let target = Path::new(extends);
+        let target = if target.is_absolute() {
+            target.to_path_buf()
+        } else {
+            agent_lib_path.parent().unwrap_or(agent_lib_path).join(target)
+        };
+
+        let nested = target.join(".agent_library");
+        let resolved = if nested.is_dir() { nested } else { target };
+
+        if !resolved.join("agent_index.yml").is_file() {
+            return Err(anyhow::anyhow!(
+                "Base library '{extends}' not found: {} does not contain agent_index.yml",
+                resolved.display()
+            ));
+        }
+
+        resolved
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {}", resolved.display()))
+    }
+
+    // 継承元にローカルのエンドポイントを重ねる（同じIDは置き換え、新しいIDは追加）
+    fn merge_libraries(base: AgentLibrary, local: AgentLibrary) -> AgentLibrary {
+        let AgentLibrary { index: base_index, prompts: base_prompts, resources: base_resources, .. } = base;
+        let AgentLibrary { index: mut local_index, base_path, prompts: local_prompts, resources: local_resources, .. } = local;
+
+        let mut endpoints = base_index.mcp_endpoints;
+        for endpoint in local_index.mcp_endpoints {
+            if let Some(existing) = endpoints.iter_mut().find(|e| e.id == endpoint.id) {
+                *existing = endpoint;
+            } else {
+                endpoints.push(endpoint);
+            }
+        }
+
+        let mut prompts = base_prompts;
+        for prompt in local_prompts {
+            if let Some(existing) = prompts.iter_mut().find(|p| p.id == prompt.id) {
+                *existing = prompt;
+            } else {
+                prompts.push(prompt);
+            }
+        }
+
+        let mut resources = base_resources;
+        for resource in local_resources {
+            if let Some(existing) = resources.iter_mut().find(|r| r.uri == resource.uri) {
+                *existing = resource;
+            } else {
+                resources.push(resource);
+            }
+        }
+
+        // ライブラリのメタデータはローカルの値を優先し、未指定なら継承元の値を使う
+        local_index.name = local_index.name.or(base_index.name);
+        local_index.description = local_index.description.or(base_index.description);
+        local_index.version = local_index.version.or(base_index.version);
+        local_index.lint.inherit(base_index.lint);
+        local_index.mcp_endpoints = endpoints;
+
+        AgentLibrary {
+            index: local_index,
+            base_path,
+            prompts,
+            resources,
+            taxonomy: Taxonomy::default(),
+        }
+    }
+
+    /// ライブラリを検証し、見つかった問題を診断の一覧として返す
+    #[must_use] pub fn validate(repo_path: &Path) -> Vec<Diagnostic> {
+        validator::validate(&repo_path.join(".agent_library"))
+    }
+
+    /// ライブラリを読み込み、`lint:` の設定に従ってリントする
+    pub fn lint(repo_path: &Path) -> Result<Vec<Diagnostic>> {
+        let library = Self::parse(repo_path)?;
+        Ok(lint::lint(&library))
+    }
+
     fn parse_index(agent_lib_path: &Path) -> Result<AgentIndex> {
         let index_path = agent_lib_path.join("agent_index.yml");
         
@@ -73,57 +214,261 @@ impl AgentLibraryParser {
         Ok(index)
     }
 
+    // ライブラリのメタデータを検証（version はセマンティックバージョン）。結果はバリデーターの診断として報告する
+    pub(crate) fn validate_index_metadata(index: &AgentIndex) -> Result<()> {
+        if let Some(name) = &index.name {
+            if name.trim().is_empty() {
+                return Err(anyhow::anyhow!("Library name must not be empty"));
+            }
+        }
+
+        if let Some(version) = &index.version {
+            semver::Version::parse(version).map_err(|e| {
+                anyhow::anyhow!("Invalid version '{version}': expected semantic version like 1.0.0 ({e})")
+            })?;
+        }
+
+        Ok(())
+    }
+
     fn parse_prompts(agent_lib_path: &Path, index: &AgentIndex) -> Result<Vec<Prompt>> {
         let mut prompts = Vec::new();
 
         for endpoint in &index.mcp_endpoints {
-            let prompt_path = agent_lib_path.join(&endpoint.prompt_file);
-            
-            if !prompt_path.exists() {
-                eprintln!("Warning: Prompt file {} not found", prompt_path.display());
+            if let Some(prompt) = Self::parse_prompt(agent_lib_path, endpoint)? {
+                prompts.push(prompt);
+            }
+        }
+
+        Ok(prompts)
+    }
+
+    // 1つのエンドポイントのプロンプトを読み込む（ファイルが無い場合は None）
+    fn parse_prompt(agent_lib_path: &Path, endpoint: &McpEndpoint) -> Result<Option<Prompt>> {
+        let prompt_path = agent_lib_path.join(&endpoint.prompt_file);
+
+        if !prompt_path.exists() {
+            tracing::warn!("Prompt file {} not found", prompt_path.display());
+            return Ok(None);
+        }
+
+        let file = Self::read_prompt_file(agent_lib_path, &prompt_path)?;
+        // front matter をインデックスのエントリとマージ
+        let metadata = front_matter::merge(endpoint, file.front_matter.as_ref());
+
+        let mut variants = Vec::new();
+        for (locale, variant_path) in locale::variant_files(agent_lib_path, endpoint) {
+            if !variant_path.exists() {
+                tracing::warn!("Locale variant {} not found", variant_path.display());
                 continue;
             }
+            let variant = Self::read_prompt_file(agent_lib_path, &variant_path)?;
+            let front_matter = variant.front_matter.unwrap_or_default();
+            variants.push(PromptVariant {
+                locale,
+                title: front_matter.title.unwrap_or_else(|| metadata.title.clone()),
+                description: front_matter.description.unwrap_or_else(|| metadata.description.clone()),
+                content: variant.content,
+                raw_content: variant.raw_content,
+                file_path: variant_path,
+                included_files: variant.included_files,
+                messages: variant.messages,
+                summary: variant.summary,
+            });
+        }
 
-            let content = fs::read_to_string(&prompt_path)
-                .with_context(|| format!("Failed to read {}", prompt_path.display()))?;
+        Ok(Some(Prompt {
+            id: endpoint.id.clone(),
+            title: metadata.title,
+            description: metadata.description,
+            content: file.content,
+            content_hash: storage::content_hash(file.raw_content.as_bytes()),
+            raw_content: file.raw_content,
+            file_path: prompt_path,
+            source_library: agent_lib_path.to_path_buf(),
+            included_files: file.included_files,
+            messages: file.messages,
+            arguments: metadata.arguments,
+            category: metadata.category,
+            trigger: metadata.trigger,
+            tags: metadata.tags,
+            variants,
+            summary: file.summary,
+            attachments: resources::resolve(agent_lib_path, &endpoint.attachments, Some(&endpoint.id)),
+            aliases: endpoint.aliases.clone(),
+            deprecated: endpoint.deprecated.clone(),
+        }))
+    }
 
-            let prompt = Prompt {
-                id: endpoint.id.clone(),
-                title: endpoint.label.clone(),
-                description: endpoint.description.clone(),
-                content,
-                file_path: prompt_path.clone(),
-            };
+    // プロンプトファイルを読み込み、front matter の分離、インクルードの展開、メッセージの分割を行う
+    fn read_prompt_file(agent_lib_path: &Path, prompt_path: &Path) -> Result<PromptFile> {
+        let raw_content = fs::read_to_string(prompt_path)
+            .with_context(|| format!("Failed to read {}", prompt_path.display()))?;
+
+        let (front_matter, body) = front_matter::split_front_matter(&raw_content)
+            .with_context(|| format!("Invalid front matter in {}", prompt_path.display()))?;
 
-            prompts.push(prompt);
+        // テンプレートの構文は配信時ではなく読み込み時に検証する（行番号は front matter を含むファイルの行）
+        if let Err(mut e) = template::parse(body) {
+            e.line += raw_content[..raw_content.len() - body.len()].matches('\n').count();
+            return Err(anyhow::anyhow!("Invalid template in {}: {e}", prompt_path.display()));
         }
 
-        Ok(prompts)
+        let expansion = include::expand_includes(body, prompt_path, agent_lib_path)
+            .with_context(|| format!("Failed to resolve includes in {}", prompt_path.display()))?;
+        let messages = messages::split_messages(&expansion.content)
+            .with_context(|| format!("Invalid message sections in {}", prompt_path.display()))?;
+
+        // インクルードしたファイルやロールの区切りをまたぐブロックは展開後の単位で検出する
+        for unit in messages.iter().map(|m| m.content.as_str()) {
+            template::parse(unit).map_err(|e| {
+                anyhow::anyhow!(
+                    "Invalid template in {} after expanding includes and message sections: {}",
+                    prompt_path.display(),
+                    e.message
+                )
+            })?;
+        }
+
+        Ok(PromptFile {
+            front_matter,
+            summary: markdown::summarize(&expansion.content),
+            content: expansion.content,
+            included_files: expansion.included_files,
+            messages,
+            raw_content,
+        })
     }
 
-    pub fn find_repositories(search_paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
-        let mut repositories = Vec::new();
+    /// 変更されたファイルに応じて読み込み済みのライブラリを部分的に更新する
+    ///
+    /// プロンプトやインクルードされたファイルの変更は該当するプロンプトだけを読み直す。
+    /// `agent_index.yml` の変更や、自動登録の対象になり得るファイルの追加はライブラリ全体を読み直す
+    pub fn apply_change(library: &mut AgentLibrary, changed_path: &Path) -> Result<LibraryUpdate> {
+        let canonical = cache::canonicalize_lenient(changed_path);
+        let is_changed = |path: &Path| path == changed_path || path == canonical;
+        // `review.ja.md` が追加された場合は `review.md` のプロンプトを読み直す
+        let variant_base = changed_path
+            .file_name()
+            .and_then(|name| locale::split_variant(&name.to_string_lossy()))
+            .map(|(base, _)| {
+                let base = changed_path.with_file_name(base);
+                let canonical = cache::canonicalize_lenient(&base);
+                (base, canonical)
+            });
+        let is_new_variant_of = |path: &Path| {
+            variant_base
+                .as_ref()
+                .is_some_and(|(base, canonical)| path == base || path == canonical)
+        };
 
-        for search_path in search_paths {
-            if !search_path.exists() {
-                continue;
+        if changed_path.file_name() == Some(std::ffi::OsStr::new("agent_index.yml")) {
+            return Self::reload(library).map(|()| LibraryUpdate::Reloaded);
+        }
+
+        let affected: Vec<String> = library
+            .prompts
+            .iter()
+            .filter(|p| {
+                is_changed(&p.file_path)
+                    || p.included_files.iter().any(|f| is_changed(f))
+                    || p.variants.iter().any(|v| {
+                        is_changed(&v.file_path) || v.included_files.iter().any(|f| is_changed(f))
+                    })
+                    || is_new_variant_of(&p.file_path)
+            })
+            .map(|p| p.id.clone())
+            .collect();
+
+        if affected.is_empty() {
+            // 未読み込みのプロンプトファイルが作成された場合や、自動登録の対象になる場合は全体を読み直す
+            let is_markdown = changed_path.extension().and_then(|e| e.to_str()) == Some("md");
+            let referenced = library
+                .index
+                .mcp_endpoints
+                .iter()
+                .any(|e| is_changed(&library.base_path.join(&e.prompt_file)));
+            if is_markdown && (referenced || !library.index.include.is_empty()) {
+                return Self::reload(library).map(|()| LibraryUpdate::Reloaded);
+            }
+            // リソースの内容は読み出し時に読むため、追加・削除された場合だけ読み直す
+            let declared = library
+                .index
+                .resources
+                .iter()
+                .chain(library.index.mcp_endpoints.iter().flat_map(|e| &e.attachments))
+                .any(|r| is_changed(&library.base_path.join(&r.path)));
+            let loaded = library
+                .resources
+                .iter()
+                .chain(library.prompts.iter().flat_map(|p| &p.attachments))
+                .any(|r| is_changed(&r.file_path));
+            if declared && loaded != changed_path.exists() {
+                return Self::reload(library).map(|()| LibraryUpdate::Reloaded);
             }
+            return Ok(LibraryUpdate::Unchanged);
+        }
 
-            for entry in WalkDir::new(search_path)
-                .max_depth(3) // Limit depth to avoid deep recursion
-                .into_iter()
-                .filter_map(std::result::Result::ok)
-            {
-                let path = entry.path();
-                if path.is_dir() && path.file_name() == Some(std::ffi::OsStr::new(".agent_library")) {
-                    if let Some(parent) = path.parent() {
-                        repositories.push(parent.to_path_buf());
-                    }
+        for id in &affected {
+            let Some(position) = library.prompts.iter().position(|p| &p.id == id) else {
+                continue;
+            };
+            let Some(endpoint) = library.index.mcp_endpoints.iter().find(|e| &e.id == id) else {
+                continue;
+            };
+
+            let source_library = library.prompts[position].source_library.clone();
+            match Self::parse_prompt(&source_library, endpoint)? {
+                Some(prompt) => library.prompts[position] = prompt,
+                None => {
+                    library.prompts.remove(position);
                 }
             }
         }
 
-        Ok(repositories)
+        library.taxonomy = Taxonomy::build(&library.prompts);
+        if !cache::refresh(&library.base_path, library, &[changed_path.to_path_buf()]) {
+            // ファイル監視がキャッシュを破棄した後は、更新したライブラリを登録し直す
+            let roots = Self::library_roots(&library.base_path)?;
+            cache::store(&library.base_path, library, &roots);
+        }
+        Ok(LibraryUpdate::Prompts(affected))
+    }
+
+    // ライブラリと extends で継承したライブラリの正規化したパス（`load_library` が集めるものと同じ）
+    fn library_roots(agent_lib_path: &Path) -> Result<Vec<PathBuf>> {
+        let mut roots: Vec<PathBuf> = Vec::new();
+        let mut current = agent_lib_path.to_path_buf();
+        loop {
+            let canonical = current
+                .canonicalize()
+                .with_context(|| format!("Failed to resolve {}", current.display()))?;
+            if roots.contains(&canonical) {
+                break;
+            }
+            roots.push(canonical);
+            match Self::parse_index(&current)?.extends {
+                Some(extends) => current = Self::resolve_extends(&extends, &current)?,
+                None => break,
+            }
+        }
+        Ok(roots)
+    }
+
+    // ライブラリ全体を読み直してキャッシュを更新する
+    fn reload(library: &mut AgentLibrary) -> Result<()> {
+        let agent_lib_path = library.base_path.clone();
+        let mut roots = Vec::new();
+        *library = Self::load_library(&agent_lib_path, &mut Vec::new(), &mut roots)?;
+        cache::store(&agent_lib_path, library, &roots);
+        Ok(())
+    }
+
+    /// 既定の設定で `.agent_library` を持つリポジトリを探す
+    ///
+    /// 詳しい設定やライブラリの情報が必要な場合は `search::search_repositories` を使う
+    pub fn find_repositories(search_paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        Ok(search::find_library_roots(search_paths, &SearchOptions::default()))
     }
 }
 
@@ -166,6 +511,9 @@ mcp_endpoints:
         assert!(result.is_ok());
 
         let library = result.unwrap();
+        assert_eq!(library.index.name.as_deref(), Some("Test Library"));
+        assert_eq!(library.index.description.as_deref(), Some("Test Agent Library"));
+        assert_eq!(library.index.version.as_deref(), Some("1.0.0"));
         assert_eq!(library.index.mcp_endpoints.len(), 1);
         assert_eq!(library.prompts.len(), 1);
         assert_eq!(library.prompts[0].id, "test_prompt");
@@ -195,6 +543,21 @@ mcp_endpoints:
         assert!(result.unwrap_err().to_string().contains("agent_index.yml not found"));
     }
 
+    #[test]
+    fn test_parse_invalid_version() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_agent_library(temp_dir.path()).unwrap();
+
+        let index_content = "name: \"Test Library\"\nversion: \"1.0\"\nmcp_endpoints: []\n";
+        fs::write(temp_dir.path().join(".agent_library/agent_index.yml"), index_content).unwrap();
+
+        // セマンティックバージョンでなくても読み込めるが、診断として報告される
+        let library = AgentLibraryParser::parse(temp_dir.path()).unwrap();
+        assert_eq!(library.index.version.as_deref(), Some("1.0"));
+        let error = AgentLibraryParser::validate_index_metadata(&library.index).unwrap_err();
+        assert!(error.to_string().contains("Invalid version '1.0'"));
+    }
+
     #[test]
     fn test_find_repositories() {
         let temp_dir = TempDir::new().unwrap();
@@ -223,6 +586,341 @@ mcp_endpoints:
         assert!(repositories.contains(&repo3));
     }
 
+    #[test]
+    fn test_parse_prompt_with_front_matter() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_agent_library(temp_dir.path()).unwrap();
+
+        let prompt_content = "---\ndescription: \"From front matter\"\ntags: [testing]\narguments:\n  - name: target\n    required: true\n---\n# Test Prompt\n\nReview {{target}}.";
+        fs::write(temp_dir.path().join(".agent_library/test_prompt.md"), prompt_content).unwrap();
+
+        let library = AgentLibraryParser::parse(temp_dir.path()).unwrap();
+        let prompt = &library.prompts[0];
+
+        // front matter は本文から取り除かれ、インデックスより優先される
+        assert_eq!(prompt.content, "# Test Prompt\n\nReview {{target}}.");
+        assert_eq!(prompt.raw_content, prompt_content);
+        assert_eq!(prompt.title, "Test Prompt");
+        assert_eq!(prompt.description, "From front matter");
+        assert_eq!(prompt.tags, vec!["testing".to_string()]);
+        assert_eq!(prompt.arguments.len(), 1);
+        assert!(prompt.arguments[0].required);
+    }
+
+    #[test]
+    fn test_parse_prompt_with_include() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_agent_library(temp_dir.path()).unwrap();
+
+        let agent_lib_dir = temp_dir.path().join(".agent_library");
+        fs::create_dir_all(agent_lib_dir.join("shared")).unwrap();
+        fs::write(agent_lib_dir.join("shared/style.md"), "Use idiomatic Rust.\n").unwrap();
+        fs::write(agent_lib_dir.join("test_prompt.md"), "# Test Prompt\n\n{{> shared/style.md}}").unwrap();
+
+        let library = AgentLibraryParser::parse(temp_dir.path()).unwrap();
+        assert_eq!(library.prompts[0].content, "# Test Prompt\n\nUse idiomatic Rust.");
+        assert_eq!(library.prompts[0].included_files.len(), 1);
+
+        // 存在しないファイルのインクルードはエラー
+        let other_dir = TempDir::new().unwrap();
+        create_test_agent_library(other_dir.path()).unwrap();
+        fs::write(other_dir.path().join(".agent_library/test_prompt.md"), "{{> shared/missing.md}}").unwrap();
+        let error = AgentLibraryParser::parse(other_dir.path()).unwrap_err();
+        assert!(format!("{error:#}").contains("Included file 'shared/missing.md' not found"));
+    }
+
+    #[test]
+    fn test_parse_prompt_with_role_sections() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_agent_library(temp_dir.path()).unwrap();
+
+        let prompt_content = "Summarize the diff.\n\n<!-- role: assistant -->\nSummary:";
+        fs::write(temp_dir.path().join(".agent_library/test_prompt.md"), prompt_content).unwrap();
+
+        let library = AgentLibraryParser::parse(temp_dir.path()).unwrap();
+        let messages = &library.prompts[0].messages;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, "user");
+        assert_eq!(messages[1].role, "assistant");
+        assert_eq!(messages[1].content, "Summary:");
+    }
+
+    #[test]
+    fn test_parse_locale_variants() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_agent_library(temp_dir.path()).unwrap();
+
+        let agent_lib_dir = temp_dir.path().join(".agent_library");
+        let index_content = "name: \"Test Library\"\nmcp_endpoints:\n  - id: \"test_prompt\"\n    label: \"Test Prompt\"\n    prompt_file: \"test_prompt.md\"\n    locales:\n      en: \"english/test_prompt.md\"\n";
+        fs::write(agent_lib_dir.join("agent_index.yml"), index_content).unwrap();
+        fs::write(agent_lib_dir.join("test_prompt.ja.md"), "---\ntitle: テスト\n---\n# テスト").unwrap();
+        fs::create_dir_all(agent_lib_dir.join("english")).unwrap();
+        fs::write(agent_lib_dir.join("english/test_prompt.md"), "# Test in English").unwrap();
+
+        let mut library = AgentLibraryParser::parse(temp_dir.path()).unwrap();
+        let variants = &library.prompts[0].variants;
+        let locales: Vec<_> = variants.iter().map(|v| v.locale.as_str()).collect();
+        assert_eq!(locales, vec!["en", "ja"]);
+        assert_eq!(variants[0].content, "# Test in English");
+        assert_eq!(variants[0].title, "Test Prompt");
+        assert_eq!(variants[1].title, "テスト");
+        assert_eq!(variants[1].content, "# テスト");
+
+        // ロケール別ファイルの変更は基本のプロンプトを読み直す
+        fs::write(agent_lib_dir.join("test_prompt.ja.md"), "# テスト（更新）").unwrap();
+        let update = AgentLibraryParser::apply_change(&mut library, &agent_lib_dir.join("test_prompt.ja.md")).unwrap();
+        assert_eq!(update, LibraryUpdate::Prompts(vec!["test_prompt".to_string()]));
+        assert_eq!(library.prompts[0].variants[1].content, "# テスト（更新）");
+    }
+
+    #[test]
+    fn test_lint_with_index_config() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_agent_library(temp_dir.path()).unwrap();
+
+        let agent_lib_dir = temp_dir.path().join(".agent_library");
+        let index_content = "name: \"Test Library\"\nlint:\n  max_prompt_chars: 5\n  rules:\n    prompt-size: error\n    undeclared-placeholder: off\nmcp_endpoints:\n  - id: \"test_prompt\"\n    label: \"Test Prompt\"\n    description: \"A test prompt\"\n    prompt_file: \"test_prompt.md\"\n";
+        fs::write(agent_lib_dir.join("agent_index.yml"), index_content).unwrap();
+        fs::write(agent_lib_dir.join("test_prompt.md"), "Review {{file}}").unwrap();
+
+        let diagnostics = AgentLibraryParser::lint(temp_dir.path()).unwrap();
+        let codes: Vec<_> = diagnostics.iter().map(|d| d.code.as_str()).collect();
+        assert_eq!(codes, vec!["prompt-size"]);
+        assert_eq!(diagnostics[0].severity, crate::agent_library::Severity::Error);
+    }
+
+    #[test]
+    fn test_parse_discovered_prompts() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_agent_library(temp_dir.path()).unwrap();
+
+        let agent_lib_dir = temp_dir.path().join(".agent_library");
+        let index_content = "name: \"Test Library\"\ninclude: [\"prompts/*.md\"]\nmcp_endpoints:\n  - id: \"test_prompt\"\n    label: \"Explicit\"\n    prompt_file: \"test_prompt.md\"\n";
+        fs::write(agent_lib_dir.join("agent_index.yml"), index_content).unwrap();
+        fs::create_dir_all(agent_lib_dir.join("prompts")).unwrap();
+        fs::write(agent_lib_dir.join("prompts/test_prompt.md"), "# Discovered duplicate").unwrap();
+        fs::write(agent_lib_dir.join("prompts/commit.md"), "---\ntitle: Commit Message\n---\n# Commit").unwrap();
+
+        let library = AgentLibraryParser::parse(temp_dir.path()).unwrap();
+        let ids: Vec<_> = library.prompts.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["test_prompt", "commit", "prompts_test_prompt"]);
+        assert_eq!(library.prompts[0].title, "Explicit");
+        assert_eq!(library.prompts[1].title, "Commit Message");
+        assert!(library.index.mcp_endpoints[1].discovered);
+    }
+
+    #[test]
+    fn test_parse_extended_library() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_repo = temp_dir.path().join("base");
+        let local_repo = temp_dir.path().join("local");
+        create_test_agent_library(&base_repo).unwrap();
+        create_test_agent_library(&local_repo).unwrap();
+
+        let base_dir = base_repo.join(".agent_library");
+        let base_index = "name: \"Base\"\nversion: \"2.0.0\"\nmcp_endpoints:\n  - id: shared\n    label: Shared\n    prompt_file: shared.md\n  - id: test_prompt\n    label: Base Prompt\n    prompt_file: test_prompt.md\n";
+        fs::write(base_dir.join("agent_index.yml"), base_index).unwrap();
+        fs::write(base_dir.join("shared.md"), "# Shared").unwrap();
+
+        let local_dir = local_repo.join(".agent_library");
+        let local_index = "name: \"Local\"\nextends: \"../base\"\nmcp_endpoints:\n  - id: test_prompt\n    label: Local Prompt\n    prompt_file: test_prompt.md\n  - id: extra\n    label: Extra\n    prompt_file: test_prompt.md\n";
+        fs::write(local_dir.join("agent_index.yml"), local_index).unwrap();
+
+        let library = AgentLibraryParser::parse(&local_repo).unwrap();
+        let ids: Vec<_> = library.prompts.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["shared", "test_prompt", "extra"]);
+        assert_eq!(library.prompts[0].source_library, base_dir.canonicalize().unwrap());
+        assert_eq!(library.prompts[1].title, "Local Prompt");
+        assert_eq!(library.prompts[1].source_library, local_dir);
+        assert_eq!(library.index.name.as_deref(), Some("Local"));
+        assert_eq!(library.index.version.as_deref(), Some("2.0.0"));
+        assert_eq!(library.index.mcp_endpoints.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_extension_cycle() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_a = temp_dir.path().join("a");
+        let repo_b = temp_dir.path().join("b");
+        create_test_agent_library(&repo_a).unwrap();
+        create_test_agent_library(&repo_b).unwrap();
+
+        fs::write(repo_a.join(".agent_library/agent_index.yml"), "extends: \"../b\"\nmcp_endpoints: []\n").unwrap();
+        fs::write(repo_b.join(".agent_library/agent_index.yml"), "extends: \"../a\"\nmcp_endpoints: []\n").unwrap();
+
+        let error = AgentLibraryParser::parse(&repo_a).unwrap_err();
+        assert!(format!("{error:#}").contains("Library extension cycle detected"));
+    }
+
+    #[test]
+    fn test_cache_detects_in_place_edits() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_agent_library(temp_dir.path()).unwrap();
+
+        let library = AgentLibraryParser::parse(temp_dir.path()).unwrap();
+        assert!(library.prompts[0].content.contains("unit testing"));
+
+        // ディレクトリの更新時刻が変わらない上書きでも変更を検出する
+        let prompt_path = temp_dir.path().join(".agent_library/test_prompt.md");
+        fs::write(&prompt_path, "# Edited prompt with different length").unwrap();
+
+        let library = AgentLibraryParser::parse(temp_dir.path()).unwrap();
+        assert_eq!(library.prompts[0].content, "# Edited prompt with different length");
+    }
+
+    #[test]
+    fn test_cache_invalidate_and_clear() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_agent_library(temp_dir.path()).unwrap();
+        let agent_lib_dir = temp_dir.path().join(".agent_library");
+
+        AgentLibraryParser::parse(temp_dir.path()).unwrap();
+        assert!(cache::lookup(&agent_lib_dir).is_some());
+
+        AgentLibraryParser::invalidate(&agent_lib_dir.join("test_prompt.md"));
+        assert!(cache::lookup(&agent_lib_dir).is_none());
+
+        AgentLibraryParser::parse(temp_dir.path()).unwrap();
+        AgentLibraryParser::invalidate(temp_dir.path());
+        assert!(cache::lookup(&agent_lib_dir).is_none());
+
+        AgentLibraryParser::parse(temp_dir.path()).unwrap();
+        AgentLibraryParser::clear();
+        assert!(cache::lookup(&agent_lib_dir).is_none());
+    }
+
+    #[test]
+    fn test_apply_change_updates_single_prompt() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_agent_library(temp_dir.path()).unwrap();
+        let agent_lib_dir = temp_dir.path().join(".agent_library");
+
+        let index_content = "name: \"Test Library\"\nmcp_endpoints:\n  - id: a\n    label: A\n    prompt_file: a.md\n  - id: b\n    label: B\n    prompt_file: b.md\n";
+        fs::write(agent_lib_dir.join("agent_index.yml"), index_content).unwrap();
+        fs::create_dir_all(agent_lib_dir.join("shared")).unwrap();
+        fs::write(agent_lib_dir.join("shared/style.md"), "Style v1").unwrap();
+        fs::write(agent_lib_dir.join("a.md"), "# A\n{{> shared/style.md}}").unwrap();
+        fs::write(agent_lib_dir.join("b.md"), "# B").unwrap();
+
+        let mut library = AgentLibraryParser::parse(temp_dir.path()).unwrap();
+
+        // プロンプトファイルの変更は該当プロンプトだけを読み直す
+        fs::write(agent_lib_dir.join("b.md"), "# B edited").unwrap();
+        let update = AgentLibraryParser::apply_change(&mut library, &agent_lib_dir.join("b.md")).unwrap();
+        assert_eq!(update, LibraryUpdate::Prompts(vec!["b".to_string()]));
+        assert_eq!(library.prompts[1].content, "# B edited");
+
+        // インクルードされたファイルの変更はインクルード元を読み直す
+        fs::write(agent_lib_dir.join("shared/style.md"), "Style v2").unwrap();
+        let update = AgentLibraryParser::apply_change(&mut library, &agent_lib_dir.join("shared/style.md")).unwrap();
+        assert_eq!(update, LibraryUpdate::Prompts(vec!["a".to_string()]));
+        assert_eq!(library.prompts[0].content, "# A\nStyle v2");
+
+        // 削除されたプロンプトは取り除く
+        fs::remove_file(agent_lib_dir.join("b.md")).unwrap();
+        AgentLibraryParser::apply_change(&mut library, &agent_lib_dir.join("b.md")).unwrap();
+        assert_eq!(library.prompts.len(), 1);
+
+        // インデックスの変更はライブラリ全体を読み直す
+        fs::write(agent_lib_dir.join("b.md"), "# B restored").unwrap();
+        let update = AgentLibraryParser::apply_change(&mut library, &agent_lib_dir.join("agent_index.yml")).unwrap();
+        assert_eq!(update, LibraryUpdate::Reloaded);
+        assert_eq!(library.prompts.len(), 2);
+
+        let update = AgentLibraryParser::apply_change(&mut library, &agent_lib_dir.join("notes.txt")).unwrap();
+        assert_eq!(update, LibraryUpdate::Unchanged);
+    }
+
+    #[test]
+    fn test_apply_change_after_watcher_invalidation() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = temp_dir.path().join("base/.agent_library");
+        fs::create_dir_all(&base_dir).unwrap();
+        fs::write(
+            base_dir.join("agent_index.yml"),
+            "name: \"Base\"\nmcp_endpoints:\n  - id: base\n    label: Base\n    prompt_file: base.md\n",
+        )
+        .unwrap();
+        fs::write(base_dir.join("base.md"), "# Base").unwrap();
+
+        let repo = temp_dir.path().join("repo");
+        let agent_lib_dir = repo.join(".agent_library");
+        fs::create_dir_all(&agent_lib_dir).unwrap();
+        fs::write(
+            agent_lib_dir.join("agent_index.yml"),
+            "name: \"Local\"\nextends: ../base\nmcp_endpoints:\n  - id: a\n    label: A\n    prompt_file: a.md\n",
+        )
+        .unwrap();
+        fs::write(agent_lib_dir.join("a.md"), "# A").unwrap();
+
+        let mut library = AgentLibraryParser::parse(&repo).unwrap();
+
+        // ファイル監視が先にキャッシュを破棄しても、部分更新した結果がキャッシュに残る
+        fs::write(agent_lib_dir.join("a.md"), "# A edited").unwrap();
+        AgentLibraryParser::invalidate(&agent_lib_dir.join("a.md"));
+        let update = AgentLibraryParser::apply_change(&mut library, &agent_lib_dir.join("a.md")).unwrap();
+        assert_eq!(update, LibraryUpdate::Prompts(vec!["a".to_string()]));
+        let cached = cache::lookup(&agent_lib_dir).unwrap();
+        assert_eq!(cached.prompts.iter().find(|p| p.id == "a").unwrap().content, "# A edited");
+
+        // 継承元のファイルの変更も引き続き検出する
+        fs::write(base_dir.join("base.md"), "# Base edited").unwrap();
+        assert!(cache::lookup(&agent_lib_dir).is_none());
+    }
+
+    #[test]
+    fn test_parse_reports_template_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_agent_library(temp_dir.path()).unwrap();
+        let agent_lib_dir = temp_dir.path().join(".agent_library");
+
+        fs::write(
+            agent_lib_dir.join("test_prompt.md"),
+            "---\ntitle: Test\n---\n# Test\n{{#if language}}\nUse {{language}}.\n",
+        )
+        .unwrap();
+        let error = format!("{:#}", AgentLibraryParser::parse(temp_dir.path()).unwrap_err());
+        assert!(error.contains("test_prompt.md: line 5, column 1: Unclosed '{{#if language}}'"), "{error}");
+
+        // 本文とインクルードの間で対応が取れていない場合
+        fs::write(agent_lib_dir.join("closing.md"), "{{/each}}").unwrap();
+        fs::write(agent_lib_dir.join("test_prompt.md"), "{{#if a}}\n{{> closing.md}}\n{{/if}}").unwrap();
+        AgentLibraryParser::invalidate(&agent_lib_dir);
+        let error = format!("{:#}", AgentLibraryParser::parse(temp_dir.path()).unwrap_err());
+        assert!(error.contains("after expanding includes"), "{error}");
+    }
+
+    #[test]
+    fn test_parse_resources() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_agent_library(temp_dir.path()).unwrap();
+        let agent_lib_dir = temp_dir.path().join(".agent_library");
+
+        let index_content = "name: \"Test Library\"\nresources:\n  - path: logo.png\n    description: Team logo\nmcp_endpoints:\n  - id: review\n    label: Review\n    prompt_file: review.md\n    attachments:\n      - schemas/review.json\n";
+        fs::write(agent_lib_dir.join("agent_index.yml"), index_content).unwrap();
+        fs::write(agent_lib_dir.join("review.md"), "# Review").unwrap();
+        fs::create_dir_all(agent_lib_dir.join("schemas")).unwrap();
+        fs::write(agent_lib_dir.join("schemas/review.json"), "{}").unwrap();
+
+        let mut library = AgentLibraryParser::parse(temp_dir.path()).unwrap();
+        assert!(library.resources.is_empty());
+        let attachment = &library.prompts[0].attachments[0];
+        assert_eq!(attachment.uri, "agent_library://files/schemas/review.json");
+        assert_eq!(attachment.mime_type, "application/json");
+        assert_eq!(attachment.prompt_id.as_deref(), Some("review"));
+
+        // 宣言済みのリソースが作成されたら読み直す
+        fs::write(agent_lib_dir.join("logo.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        let update = AgentLibraryParser::apply_change(&mut library, &agent_lib_dir.join("logo.png")).unwrap();
+        assert_eq!(update, LibraryUpdate::Reloaded);
+        assert_eq!(library.resources[0].description.as_deref(), Some("Team logo"));
+
+        // 内容の変更は読み出し時に反映されるため読み直さない
+        fs::write(agent_lib_dir.join("schemas/review.json"), "{\"v\": 2}").unwrap();
+        let update = AgentLibraryParser::apply_change(&mut library, &agent_lib_dir.join("schemas/review.json")).unwrap();
+        assert_eq!(update, LibraryUpdate::Unchanged);
+    }
+
     #[test]
     fn test_cache_functionality() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,147 @@
+use super::types::{LibraryResource, ResourceEntry};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// ファイルを公開するリソースの URI の接頭辞
+pub const RESOURCE_URI_PREFIX: &str = "agent_library://files/";
+
+/// リソースの内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceContent {
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+/// `attachments:` / `resources:` のエントリを `.agent_library` 内のファイルに解決する
+///
+/// 存在しないファイルや `.agent_library` の外を指すエントリは警告を出して無視する
+#[must_use] pub fn resolve(agent_lib_path: &Path, entries: &[ResourceEntry], prompt_id: Option<&str>) -> Vec<LibraryResource> {
+    entries
+        .iter()
+        .filter_map(|entry| match resolve_path(agent_lib_path, &entry.path) {
+            Ok(file_path) => Some(LibraryResource {
+                uri: uri_for(&entry.path),
+                name: entry.name.clone().unwrap_or_else(|| file_name(&entry.path)),
+                description: entry.description.clone(),
+                mime_type: entry.mime_type.clone().unwrap_or_else(|| detect_mime_type(&file_path)),
+                prompt_id: prompt_id.map(ToString::to_string),
+                file_path,
+            }),
+            Err(e) => {
+                tracing::warn!("Skipping resource '{}': {e:#}", entry.path);
+                None
+            }
+        })
+        .collect()
+}
+
+/// リソースのパスを検証し、正規化したパスを返す
+pub fn resolve_path(agent_lib_path: &Path, relative: &str) -> Result<PathBuf> {
+    let root = agent_lib_path
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", agent_lib_path.display()))?;
+    let path = root
+        .join(relative)
+        .canonicalize()
+        .map_err(|_| anyhow::anyhow!("Resource file '{relative}' not found"))?;
+
+    if !path.starts_with(&root) {
+        return Err(anyhow::anyhow!("Resource file '{relative}' is outside .agent_library"));
+    }
+    if !path.is_file() {
+        return Err(anyhow::anyhow!("Resource '{relative}' is not a file"));
+    }
+    Ok(path)
+}
+
+/// 拡張子から MIME タイプを推測し、分からない場合は内容から判定する
+#[must_use] pub fn detect_mime_type(path: &Path) -> String {
+    if let Some(mime) = mime_guess::from_path(path).first() {
+        return mime.essence_str().to_string();
+    }
+    match fs::read(path) {
+        Ok(bytes) if is_text(&bytes) => "text/plain".to_string(),
+        _ => "application/octet-stream".to_string(),
+    }
+}
+
+/// リソースを読み込む。テキストとして扱える内容は文字列、それ以外はバイト列で返す
+pub fn read(resource: &LibraryResource) -> Result<ResourceContent> {
+    let bytes = fs::read(&resource.file_path)
+        .with_context(|| format!("Failed to read {}", resource.file_path.display()))?;
+
+    if is_text_mime_type(&resource.mime_type) || is_text(&bytes) {
+        if let Ok(text) = String::from_utf8(bytes.clone()) {
+            return Ok(ResourceContent::Text(text));
+        }
+    }
+    Ok(ResourceContent::Blob(bytes))
+}
+
+fn uri_for(relative: &str) -> String {
+    format!("{RESOURCE_URI_PREFIX}{}", relative.replace('\\', "/").trim_start_matches("./"))
+}
+
+fn file_name(relative: &str) -> String {
+    Path::new(relative)
+        .file_name()
+        .map_or_else(|| relative.to_string(), |n| n.to_string_lossy().to_string())
+}
+
+fn is_text_mime_type(mime_type: &str) -> bool {
+    mime_type.starts_with("text/")
+        || ["json", "xml", "yaml", "toml", "javascript", "x-sh"]
+            .iter()
+            .any(|suffix| mime_type.ends_with(suffix))
+}
+
+// NUL を含まない UTF-8 はテキストとして扱う
+fn is_text(bytes: &[u8]) -> bool {
+    !bytes.contains(&0) && std::str::from_utf8(bytes).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(path: &str) -> ResourceEntry {
+        ResourceEntry {
+            path: path.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_resources() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join(".agent_library");
+        fs::create_dir_all(root.join("schemas")).unwrap();
+        fs::write(root.join("schemas/review.json"), "{}").unwrap();
+        fs::write(temp_dir.path().join("secret.txt"), "secret").unwrap();
+
+        let entries = vec![entry("schemas/review.json"), entry("missing.txt"), entry("../secret.txt")];
+        let resources = resolve(&root, &entries, Some("review"));
+
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].uri, "agent_library://files/schemas/review.json");
+        assert_eq!(resources[0].name, "review.json");
+        assert_eq!(resources[0].mime_type, "application/json");
+        assert_eq!(resources[0].prompt_id.as_deref(), Some("review"));
+    }
+
+    #[test]
+    fn test_read_text_and_binary() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("checklist"), "- [ ] tests").unwrap();
+        fs::write(root.join("logo.png"), [0x89, b'P', b'N', b'G', 0, 1]).unwrap();
+
+        let resources = resolve(root, &[entry("checklist"), entry("logo.png")], None);
+        assert_eq!(resources[0].mime_type, "text/plain");
+        assert_eq!(read(&resources[0]).unwrap(), ResourceContent::Text("- [ ] tests".to_string()));
+        assert_eq!(resources[1].mime_type, "image/png");
+        assert_eq!(read(&resources[1]).unwrap(), ResourceContent::Blob(vec![0x89, b'P', b'N', b'G', 0, 1]));
+    }
+}
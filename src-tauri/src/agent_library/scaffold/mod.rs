@@ -0,0 +1,294 @@
+use super::index_file;
+use super::parser::AgentLibraryParser;
+use super::types::{McpEndpoint, PromptArgument};
+use super::validator::{self, Severity};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+// 書き込み中のライブラリ（検証してから `.agent_library` に名前を変える）
+const STAGING_DIR: &str = ".agent_library.scaffold";
+
+/// 組み込みのテンプレート
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BuiltinTemplate {
+    CodeReview,
+    CommitMessage,
+    TestWriting,
+}
+
+impl BuiltinTemplate {
+    pub const ALL: [Self; 3] = [Self::CodeReview, Self::CommitMessage, Self::TestWriting];
+
+    fn endpoint(self) -> McpEndpoint {
+        let argument = |name: &str, description: &str, required: bool| PromptArgument {
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            required,
+            default: None,
+        };
+        let (id, label, description, category, arguments) = match self {
+            Self::CodeReview => (
+                "code_review",
+                "コードレビュー",
+                "コードの品質チェックとレビューを行う",
+                "development/review",
+                vec![
+                    argument("file", "レビュー対象のファイルや差分", true),
+                    argument("focus", "重視する観点", false),
+                ],
+            ),
+            Self::CommitMessage => (
+                "commit_message",
+                "コミットメッセージ",
+                "変更内容からコミットメッセージを作成する",
+                "development/git",
+                vec![argument("changes", "変更内容（差分や概要）", true)],
+            ),
+            Self::TestWriting => (
+                "test_writing",
+                "テストの作成",
+                "対象のコードのテストを作成する",
+                "development/testing",
+                vec![
+                    argument("target", "テスト対象の関数やファイル", true),
+                    argument("framework", "使用するテストフレームワーク", false),
+                ],
+            ),
+        };
+
+        McpEndpoint {
+            id: id.to_string(),
+            label: label.to_string(),
+            description: description.to_string(),
+            prompt_file: format!("prompts/{id}.md"),
+            trigger: Some(id.to_string()),
+            category: Some(category.to_string()),
+            arguments,
+            ..Default::default()
+        }
+    }
+
+    const fn content(self) -> &'static str {
+        match self {
+            Self::CodeReview => include_str!("templates/code_review.md"),
+            Self::CommitMessage => include_str!("templates/commit_message.md"),
+            Self::TestWriting => include_str!("templates/test_writing.md"),
+        }
+    }
+}
+
+/// ライブラリの作成オプション
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScaffoldOptions {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    // 組み込みテンプレート（空の場合はすべて）
+    pub templates: Vec<BuiltinTemplate>,
+    // ユーザー定義のテンプレート（`agent_index.yml` を含むディレクトリ）。指定した場合は組み込みテンプレートを使わない
+    pub template_dir: Option<PathBuf>,
+}
+
+/// 作成したライブラリ
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScaffoldReport {
+    // .agent_library からの相対パス
+    pub files: Vec<String>,
+    pub endpoints: Vec<String>,
+}
+
+/// リポジトリに `.agent_library` を作成する
+///
+/// 書き込んだライブラリを検証し、エラーがある場合は何も残さずにエラーを返す。
+/// 既に `.agent_library` がある場合はエラーにする
+pub fn create_library(repo_path: &Path, options: &ScaffoldOptions) -> Result<ScaffoldReport> {
+    if !repo_path.is_dir() {
+        return Err(anyhow::anyhow!("Repository {} not found", repo_path.display()));
+    }
+    let agent_lib_path = repo_path.join(".agent_library");
+    if agent_lib_path.exists() {
+        return Err(anyhow::anyhow!(".agent_library already exists in {}", repo_path.display()));
+    }
+
+    let staging = repo_path.join(STAGING_DIR);
+    if staging.exists() {
+        fs::remove_dir_all(&staging).with_context(|| format!("Failed to remove {}", staging.display()))?;
+    }
+    let result = write_library(&staging, options).and_then(|files| {
+        let errors: Vec<String> = validator::validate(&staging)
+            .into_iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| d.message)
+            .collect();
+        if !errors.is_empty() {
+            return Err(anyhow::anyhow!("Template is not a valid agent library: {}", errors.join("; ")));
+        }
+        fs::rename(&staging, &agent_lib_path)
+            .with_context(|| format!("Failed to create {}", agent_lib_path.display()))?;
+        Ok(files)
+    });
+    let files = match result {
+        Ok(files) => files,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+    };
+
+    AgentLibraryParser::invalidate(&agent_lib_path);
+    let library = AgentLibraryParser::parse(repo_path)?;
+    tracing::info!("Created agent library in {}", agent_lib_path.display());
+    Ok(ScaffoldReport {
+        files,
+        endpoints: library.prompts.into_iter().map(|p| p.id).collect(),
+    })
+}
+
+// テンプレートのファイルを書き込み、書き込んだファイルを返す
+fn write_library(target: &Path, options: &ScaffoldOptions) -> Result<Vec<String>> {
+    fs::create_dir_all(target).with_context(|| format!("Failed to create {}", target.display()))?;
+    match &options.template_dir {
+        Some(template_dir) => copy_template(template_dir, target),
+        None => write_builtin(target, options),
+    }
+}
+
+fn write_builtin(target: &Path, options: &ScaffoldOptions) -> Result<Vec<String>> {
+    let templates = if options.templates.is_empty() { &BuiltinTemplate::ALL[..] } else { &options.templates };
+
+    let mut index = Mapping::new();
+    let name = options.name.clone().filter(|n| !n.trim().is_empty()).unwrap_or_else(|| {
+        target
+            .parent()
+            .and_then(Path::file_name)
+            .map_or_else(|| "Agent Library".to_string(), |n| n.to_string_lossy().to_string())
+    });
+    index.insert("name".into(), name.into());
+    if let Some(description) = options.description.clone().filter(|d| !d.trim().is_empty()) {
+        index.insert("description".into(), description.into());
+    }
+    index.insert("version".into(), "1.0.0".into());
+
+    let mut endpoints = Vec::new();
+    let mut files = vec!["agent_index.yml".to_string()];
+    for (i, template) in templates.iter().enumerate() {
+        if templates[..i].contains(template) {
+            continue;
+        }
+        let endpoint = template.endpoint();
+        let path = target.join(&endpoint.prompt_file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&path, template.content()).with_context(|| format!("Failed to write {}", path.display()))?;
+        files.push(endpoint.prompt_file.clone());
+        endpoints.push(index_file::endpoint_value(&endpoint));
+    }
+    index.insert("mcp_endpoints".into(), Value::Sequence(endpoints));
+
+    let content = format!(
+        "# Librarian のプロンプト定義（使えるキーは README の「Agent Library構造」を参照）\n{}",
+        serde_yaml::to_string(&Value::Mapping(index))?
+    );
+    fs::write(target.join("agent_index.yml"), content).context("Failed to write agent_index.yml")?;
+    Ok(files)
+}
+
+// ユーザー定義のテンプレートをコピーする（シンボリックリンクと `.git` はコピーしない）
+fn copy_template(template_dir: &Path, target: &Path) -> Result<Vec<String>> {
+    if !template_dir.join("agent_index.yml").is_file() {
+        return Err(anyhow::anyhow!("Template {} does not contain agent_index.yml", template_dir.display()));
+    }
+
+    let mut files = Vec::new();
+    let entries = WalkDir::new(template_dir)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git");
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Failed to read {}", template_dir.display()))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(template_dir)?;
+        let destination = target.join(relative);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::copy(entry.path(), &destination)
+            .with_context(|| format!("Failed to copy {}", entry.path().display()))?;
+        files.push(relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"));
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_create_builtin_library() {
+        let temp_dir = TempDir::new().unwrap();
+        let options = ScaffoldOptions {
+            name: Some("Team Prompts".to_string()),
+            templates: vec![BuiltinTemplate::CodeReview, BuiltinTemplate::CommitMessage],
+            ..Default::default()
+        };
+
+        let report = create_library(temp_dir.path(), &options).unwrap();
+        assert_eq!(report.endpoints, vec!["code_review", "commit_message"]);
+        assert_eq!(report.files, vec!["agent_index.yml", "prompts/code_review.md", "prompts/commit_message.md"]);
+        assert!(validator::validate(&temp_dir.path().join(".agent_library")).is_empty());
+
+        let library = AgentLibraryParser::parse(temp_dir.path()).unwrap();
+        assert_eq!(library.index.name.as_deref(), Some("Team Prompts"));
+        let review = library.find_prompt("code_review").unwrap();
+        let values = HashMap::from([("file".to_string(), "src/main.rs".to_string())]);
+        let rendered = crate::agent_library::template::render(&review.content, &values);
+        assert!(rendered.contains("src/main.rs の変更をレビュー"));
+        assert!(!rendered.contains("特に次の観点"));
+
+        // 既にある場合は作成しない
+        let error = create_library(temp_dir.path(), &options).unwrap_err().to_string();
+        assert!(error.contains("already exists"), "{error}");
+    }
+
+    #[test]
+    fn test_create_from_template_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let template_dir = temp_dir.path().join("template");
+        fs::create_dir_all(template_dir.join("prompts")).unwrap();
+        fs::write(
+            template_dir.join("agent_index.yml"),
+            "# team template\nmcp_endpoints:\n  - id: deploy\n    label: Deploy\n    prompt_file: prompts/deploy.md\n",
+        )
+        .unwrap();
+        fs::write(template_dir.join("prompts/deploy.md"), "# Deploy").unwrap();
+        let repo = temp_dir.path().join("repo");
+        fs::create_dir_all(&repo).unwrap();
+
+        let options = ScaffoldOptions {
+            template_dir: Some(template_dir.clone()),
+            ..Default::default()
+        };
+        let report = create_library(&repo, &options).unwrap();
+        assert_eq!(report.endpoints, vec!["deploy"]);
+        let index = fs::read_to_string(repo.join(".agent_library/agent_index.yml")).unwrap();
+        assert!(index.starts_with("# team template"));
+
+        // 不正なテンプレートは何も残さない
+        fs::remove_file(template_dir.join("prompts/deploy.md")).unwrap();
+        let broken = temp_dir.path().join("broken");
+        fs::create_dir_all(&broken).unwrap();
+        assert!(create_library(&broken, &options).is_err());
+        assert!(!broken.join(".agent_library").exists());
+        assert!(!broken.join(STAGING_DIR).exists());
+    }
+}
@@ -0,0 +1,255 @@
+use super::parser::AgentLibraryParser;
+use super::validator::{self, Severity};
+use ignore::{WalkBuilder, WalkState};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+// 既定で探索しないディレクトリ
+const DEFAULT_SKIP_DIRS: &[&str] = &[
+    ".git",
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    "vendor",
+    ".venv",
+    "__pycache__",
+];
+
+/// リポジトリ探索の設定
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    // 探索するディレクトリの深さ（None の場合は無制限）
+    pub max_depth: Option<usize>,
+    // .gitignore / .ignore に一致するディレクトリを探索しない
+    pub respect_gitignore: bool,
+    // 名前が一致するディレクトリを探索しない
+    pub skip_dirs: Vec<String>,
+    pub follow_symlinks: bool,
+    // 探索スレッド数（0 の場合は自動）
+    pub threads: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: Some(3),
+            respect_gitignore: true,
+            skip_dirs: DEFAULT_SKIP_DIRS.iter().map(ToString::to_string).collect(),
+            follow_symlinks: false,
+            threads: 0,
+        }
+    }
+}
+
+/// 見つかったリポジトリの情報
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepositoryInfo {
+    pub path: PathBuf,
+    // agent_index.yml の name（読み込めない場合は None）
+    pub name: Option<String>,
+    pub prompt_count: usize,
+    // 読み込みに成功し、検証エラーが無い
+    pub valid: bool,
+    pub error_count: usize,
+    // .agent_library 内で最後に更新されたファイルの時刻（RFC 3339）
+    pub last_modified: Option<String>,
+}
+
+/// `.agent_library` を持つリポジトリを並列に探索し、ライブラリの情報と共に返す
+///
+/// 結果はパス順に並べる
+#[must_use] pub fn search_repositories(search_paths: &[PathBuf], options: &SearchOptions) -> Vec<RepositoryInfo> {
+    let mut repositories = walk(search_paths, options, inspect_repository);
+    repositories.sort_by(|a, b| a.path.cmp(&b.path));
+    repositories.dedup_by(|a, b| a.path == b.path);
+    repositories
+}
+
+/// `.agent_library` を持つリポジトリのパスだけを返す
+#[must_use] pub fn find_library_roots(search_paths: &[PathBuf], options: &SearchOptions) -> Vec<PathBuf> {
+    let mut repositories = walk(search_paths, options, Path::to_path_buf);
+    repositories.sort();
+    repositories.dedup();
+    repositories
+}
+
+// 見つかった各リポジトリに対して探索スレッド上で `inspect` を呼ぶ
+fn walk<T, F>(search_paths: &[PathBuf], options: &SearchOptions, inspect: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&Path) -> T + Sync,
+{
+    let roots: Vec<&PathBuf> = search_paths.iter().filter(|p| p.exists()).collect();
+    let Some((first, rest)) = roots.split_first() else {
+        return Vec::new();
+    };
+
+    let mut builder = WalkBuilder::new(first);
+    for root in rest {
+        builder.add(root);
+    }
+
+    let skip_dirs = options.skip_dirs.clone();
+    builder
+        .max_depth(options.max_depth)
+        .follow_links(options.follow_symlinks)
+        .threads(options.threads)
+        .hidden(false)
+        .parents(options.respect_gitignore)
+        .ignore(options.respect_gitignore)
+        .git_ignore(options.respect_gitignore)
+        .git_global(options.respect_gitignore)
+        .git_exclude(options.respect_gitignore)
+        .require_git(false)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            !(is_dir && skip_dirs.iter().any(|name| entry.file_name() == name.as_str()))
+        });
+
+    let found = Mutex::new(Vec::new());
+    builder.build_parallel().run(|| {
+        let found = &found;
+        let inspect = &inspect;
+        Box::new(move |entry| {
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
+            };
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            if !is_dir || entry.file_name() != ".agent_library" {
+                return WalkState::Continue;
+            }
+
+            if let Some(repository) = entry.path().parent() {
+                let result = inspect(repository);
+                if let Ok(mut found) = found.lock() {
+                    found.push(result);
+                }
+            }
+            // ライブラリの中は探索しない
+            WalkState::Skip
+        })
+    });
+
+    found.into_inner().unwrap_or_default()
+}
+
+// リポジトリのライブラリを読み込んで情報を集める
+fn inspect_repository(repository: &Path) -> RepositoryInfo {
+    let agent_lib_path = repository.join(".agent_library");
+    let error_count = validator::validate(&agent_lib_path)
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+
+    let (name, prompt_count, parsed) = match AgentLibraryParser::parse(repository) {
+        Ok(library) => (library.index.name, library.prompts.len(), true),
+        Err(e) => {
+            tracing::debug!("Failed to parse agent library in {}: {e}", repository.display());
+            (None, 0, false)
+        }
+    };
+
+    RepositoryInfo {
+        path: repository.to_path_buf(),
+        name,
+        prompt_count,
+        valid: parsed && error_count == 0,
+        error_count,
+        last_modified: last_modified(&agent_lib_path).map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339()),
+    }
+}
+
+fn last_modified(agent_lib_path: &Path) -> Option<SystemTime> {
+    WalkDir::new(agent_lib_path)
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+        .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_library(repository: &Path, name: &str) {
+        let agent_lib_dir = repository.join(".agent_library");
+        fs::create_dir_all(&agent_lib_dir).unwrap();
+        fs::write(
+            agent_lib_dir.join("agent_index.yml"),
+            format!("name: \"{name}\"\nmcp_endpoints:\n  - id: review\n    label: Review\n    prompt_file: review.md\n"),
+        )
+        .unwrap();
+        fs::write(agent_lib_dir.join("review.md"), "# Review").unwrap();
+    }
+
+    #[test]
+    fn test_search_repositories_with_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        create_library(&root.join("valid"), "Valid Library");
+
+        // プロンプトファイルが無いライブラリは無効として返す
+        create_library(&root.join("broken"), "Broken Library");
+        fs::remove_file(root.join("broken/.agent_library/review.md")).unwrap();
+
+        let repositories = search_repositories(&[root.to_path_buf()], &SearchOptions::default());
+        assert_eq!(repositories.len(), 2);
+
+        let broken = &repositories[0];
+        assert_eq!(broken.path, root.join("broken"));
+        assert!(!broken.valid);
+        assert_eq!(broken.prompt_count, 0);
+
+        let valid = &repositories[1];
+        assert_eq!(valid.name.as_deref(), Some("Valid Library"));
+        assert_eq!(valid.prompt_count, 1);
+        assert!(valid.valid);
+        assert!(valid.last_modified.is_some());
+    }
+
+    #[test]
+    fn test_search_skips_ignored_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        create_library(&root.join("project"), "Project");
+        create_library(&root.join("node_modules/package"), "Dependency");
+        create_library(&root.join("generated/output"), "Generated");
+        fs::write(root.join(".gitignore"), "generated/\n").unwrap();
+
+        let repositories = search_repositories(&[root.to_path_buf()], &SearchOptions::default());
+        let paths: Vec<_> = repositories.iter().map(|r| r.path.clone()).collect();
+        assert_eq!(paths, vec![root.join("project")]);
+
+        // 無視ルールを使わない場合は .gitignore の対象も探索する
+        let options = SearchOptions {
+            respect_gitignore: false,
+            ..Default::default()
+        };
+        let repositories = search_repositories(&[root.to_path_buf()], &options);
+        assert_eq!(repositories.len(), 2);
+    }
+
+    #[test]
+    fn test_search_max_depth() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        create_library(&root.join("a/b/c/deep"), "Deep");
+
+        let repositories = search_repositories(&[root.to_path_buf()], &SearchOptions::default());
+        assert!(repositories.is_empty());
+
+        let options = SearchOptions {
+            max_depth: None,
+            ..Default::default()
+        };
+        let repositories = search_repositories(&[root.to_path_buf()], &options);
+        assert_eq!(repositories.len(), 1);
+    }
+}
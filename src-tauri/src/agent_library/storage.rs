@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use super::history::{self, SnapshotKind};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::UNIX_EPOCH;
+
+// 一時ファイル名の重複を避けるための連番
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// ファイルの版（競合の検出に使う）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileVersion {
+    // 内容の SHA-256（16進数）
+    pub hash: String,
+    // 更新日時（UNIX エポックからのミリ秒）
+    pub modified: Option<u64>,
+}
+
+/// 保存するときに想定しているディスク上の版（両方を省略した場合は確認しない）
+///
+/// `hash` を指定した場合は `hash` だけで比較する
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExpectedVersion {
+    pub hash: Option<String>,
+    pub modified: Option<u64>,
+}
+
+/// 保存のエラー
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SaveError {
+    // 読み込んだ後にディスク上のファイルが変更された（削除された場合は `current` が無い）
+    Conflict {
+        path: PathBuf,
+        current: Option<FileVersion>,
+        disk_content: Option<String>,
+        your_content: String,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Conflict { path, .. } => write!(f, "{} was changed on disk since it was loaded", path.display()),
+            Self::Failed { message } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<anyhow::Error> for SaveError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::Failed {
+            message: format!("{error:#}"),
+        }
+    }
+}
+
+#[must_use] pub fn content_hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// ファイルの現在の版を返す（ファイルが無い場合は `None`）
+pub fn file_version(path: &Path) -> Result<Option<FileVersion>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(Some(FileVersion {
+        hash: content_hash(&content),
+        modified: modified_millis(path),
+    }))
+}
+
+/// 同じディレクトリの一時ファイルに書き込んでから名前を変えて置き換える
+///
+/// 途中で失敗しても元のファイルは壊れない。既存のファイルのパーミッションは引き継ぎ、変更前の内容は履歴に記録する
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    if let Ok(previous) = fs::read(path) {
+        if previous != content {
+            history::record(path, &previous, SnapshotKind::Edit)?;
+        }
+    }
+    replace_file(path, content)
+}
+
+// 履歴を記録せずにアトミックに置き換える（履歴自体の書き込みに使う）
+pub(super) fn replace_file(path: &Path, content: &[u8]) -> Result<()> {
+    let parent = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let temp = parent.join(format!(
+        ".{name}.{}-{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = (|| -> Result<()> {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+            .with_context(|| format!("Failed to create {}", temp.display()))?;
+        file.write_all(content).with_context(|| format!("Failed to write {}", temp.display()))?;
+        file.sync_all().with_context(|| format!("Failed to write {}", temp.display()))?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp, metadata.permissions())
+                .with_context(|| format!("Failed to set permissions of {}", temp.display()))?;
+        }
+        fs::rename(&temp, path).with_context(|| format!("Failed to replace {}", path.display()))
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// ディスク上のファイルが `expected` の版のままであることを確認してから保存し、保存後の版を返す
+pub fn save(path: &Path, content: &str, expected: &ExpectedVersion) -> Result<FileVersion, SaveError> {
+    if expected.hash.is_some() || expected.modified.is_some() {
+        let current = file_version(path)?;
+        let unchanged = current.as_ref().is_some_and(|current| match &expected.hash {
+            Some(hash) => hash.eq_ignore_ascii_case(&current.hash),
+            None => expected.modified == current.modified,
+        });
+        if !unchanged {
+            return Err(SaveError::Conflict {
+                path: path.to_path_buf(),
+                disk_content: current.as_ref().and_then(|_| fs::read_to_string(path).ok()),
+                current,
+                your_content: content.to_string(),
+            });
+        }
+    }
+
+    write_atomic(path, content.as_bytes())?;
+    Ok(FileVersion {
+        hash: content_hash(content.as_bytes()),
+        modified: modified_millis(path),
+    })
+}
+
+fn modified_millis(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let millis = modified.duration_since(UNIX_EPOCH).ok()?.as_millis();
+    u64::try_from(millis).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("review.md");
+        fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        let entries: Vec<_> = fs::read_dir(temp_dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+
+        // 書き込めない場合も元のファイルは残る
+        assert!(write_atomic(&temp_dir.path().join("missing/dir\0/file.md"), b"x").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_save_detects_conflicts() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("review.md");
+        fs::write(&path, "loaded").unwrap();
+        let loaded = file_version(&path).unwrap().unwrap();
+
+        let expected = ExpectedVersion {
+            hash: Some(loaded.hash),
+            ..Default::default()
+        };
+        let saved = save(&path, "first edit", &expected).unwrap();
+        assert_eq!(saved.hash, content_hash(b"first edit"));
+
+        // 古い版を想定した保存は競合になり、両方の内容を返す
+        match save(&path, "stale edit", &expected).unwrap_err() {
+            SaveError::Conflict { current, disk_content, your_content, .. } => {
+                assert_eq!(current.unwrap().hash, saved.hash);
+                assert_eq!(disk_content.as_deref(), Some("first edit"));
+                assert_eq!(your_content, "stale edit");
+            }
+            SaveError::Failed { message } => panic!("{message}"),
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first edit");
+
+        // 更新日時でも確認でき、削除されたファイルも競合になる
+        let by_mtime = ExpectedVersion {
+            modified: saved.modified,
+            ..Default::default()
+        };
+        assert!(save(&path, "second edit", &by_mtime).is_ok());
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(save(&path, "again", &expected), Err(SaveError::Conflict { current: None, .. })));
+        assert!(save(&path, "recreated", &ExpectedVersion::default()).is_ok());
+    }
+}
@@ -0,0 +1,190 @@
+use super::types::{AgentLibrary, Prompt};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// タグ・カテゴリ・トリガーごとのプロンプトの索引（サイドバー表示用）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Taxonomy {
+    // タグごとのプロンプトID（タグは小文字に正規化）
+    pub tags: BTreeMap<String, Vec<String>>,
+    // カテゴリの階層（名前順）
+    pub categories: Vec<CategoryNode>,
+    pub triggers: BTreeMap<String, Vec<String>>,
+}
+
+/// `development/review` のような階層カテゴリの1段
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryNode {
+    pub name: String,
+    // ルートからのパス（例: `development/review`）
+    pub path: String,
+    // このカテゴリに直接属するプロンプトのID
+    pub prompts: Vec<String>,
+    // 子孫を含むプロンプト数
+    pub total: usize,
+    pub children: Vec<Self>,
+}
+
+/// プロンプトの絞り込み条件（指定した条件をすべて満たすものを返す）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptQuery {
+    // すべてのタグを持つプロンプト
+    pub tags: Vec<String>,
+    // カテゴリの前方一致（`development` は `development/review` にも一致する）
+    pub category: Option<String>,
+    pub trigger: Option<String>,
+}
+
+impl Taxonomy {
+    /// プロンプトの一覧から索引を作る
+    #[must_use] pub fn build(prompts: &[Prompt]) -> Self {
+        let mut taxonomy = Self::default();
+        let mut root = CategoryNode::default();
+
+        for prompt in prompts {
+            for tag in &prompt.tags {
+                let ids = taxonomy.tags.entry(normalize_tag(tag)).or_default();
+                if !ids.contains(&prompt.id) {
+                    ids.push(prompt.id.clone());
+                }
+            }
+            if let Some(trigger) = prompt.trigger.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+                taxonomy.triggers.entry(trigger.to_string()).or_default().push(prompt.id.clone());
+            }
+            if let Some(category) = prompt.category.as_deref() {
+                let segments = category_segments(category);
+                if !segments.is_empty() {
+                    root.insert(&segments, &prompt.id);
+                }
+            }
+        }
+
+        taxonomy.categories = root.children;
+        taxonomy
+    }
+}
+
+impl CategoryNode {
+    fn insert(&mut self, segments: &[&str], prompt_id: &str) {
+        self.total += 1;
+        let Some((first, rest)) = segments.split_first() else {
+            self.prompts.push(prompt_id.to_string());
+            return;
+        };
+
+        let position = match self.children.binary_search_by(|c| c.name.as_str().cmp(first)) {
+            Ok(position) => position,
+            Err(position) => {
+                let path = if self.path.is_empty() { (*first).to_string() } else { format!("{}/{first}", self.path) };
+                self.children.insert(position, Self {
+                    name: (*first).to_string(),
+                    path,
+                    ..Default::default()
+                });
+                position
+            }
+        };
+        self.children[position].insert(rest, prompt_id);
+    }
+}
+
+impl PromptQuery {
+    #[must_use] pub fn matches(&self, prompt: &Prompt) -> bool {
+        let tags_match = self
+            .tags
+            .iter()
+            .all(|tag| prompt.tags.iter().any(|t| normalize_tag(t) == normalize_tag(tag)));
+        let category_match = self.category.as_deref().is_none_or(|category| {
+            let wanted = category_segments(category);
+            let actual = prompt.category.as_deref().map(category_segments).unwrap_or_default();
+            actual.starts_with(&wanted)
+        });
+        let trigger_match = self.trigger.as_deref().is_none_or(|trigger| {
+            prompt
+                .trigger
+                .as_deref()
+                .is_some_and(|t| t.trim().eq_ignore_ascii_case(trigger.trim()))
+        });
+
+        tags_match && category_match && trigger_match
+    }
+}
+
+/// 条件に一致するプロンプトを定義順に返す
+#[must_use] pub fn query<'a>(library: &'a AgentLibrary, query: &PromptQuery) -> Vec<&'a Prompt> {
+    library.prompts.iter().filter(|p| query.matches(p)).collect()
+}
+
+// カテゴリを `/` で区切った各段（前後の空白と空の段は無視する）
+fn category_segments(category: &str) -> Vec<&str> {
+    category.split('/').map(str::trim).filter(|s| !s.is_empty()).collect()
+}
+
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(id: &str, category: Option<&str>, tags: &[&str], trigger: Option<&str>) -> Prompt {
+        Prompt {
+            id: id.to_string(),
+            category: category.map(ToString::to_string),
+            tags: tags.iter().map(ToString::to_string).collect(),
+            trigger: trigger.map(ToString::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn prompts() -> Vec<Prompt> {
+        vec![
+            prompt("review", Some("development/review"), &["Rust", "quality"], Some("/review")),
+            prompt("security", Some("development/review/security"), &["quality"], None),
+            prompt("commit", Some("development"), &["git"], Some("/commit")),
+            prompt("notes", None, &[], None),
+        ]
+    }
+
+    #[test]
+    fn test_build_taxonomy() {
+        let taxonomy = Taxonomy::build(&prompts());
+
+        assert_eq!(taxonomy.tags["rust"], vec!["review".to_string()]);
+        assert_eq!(taxonomy.tags["quality"], vec!["review".to_string(), "security".to_string()]);
+        assert_eq!(taxonomy.triggers["/commit"], vec!["commit".to_string()]);
+
+        assert_eq!(taxonomy.categories.len(), 1);
+        let development = &taxonomy.categories[0];
+        assert_eq!((development.path.as_str(), development.total), ("development", 3));
+        assert_eq!(development.prompts, vec!["commit".to_string()]);
+        let review = &development.children[0];
+        assert_eq!((review.path.as_str(), review.total), ("development/review", 2));
+        assert_eq!(review.children[0].path, "development/review/security");
+    }
+
+    #[test]
+    fn test_query_prompts() {
+        let prompts = prompts();
+        let ids = |query: PromptQuery| -> Vec<String> {
+            prompts.iter().filter(|p| query.matches(p)).map(|p| p.id.clone()).collect()
+        };
+
+        assert_eq!(ids(PromptQuery::default()).len(), 4);
+        assert_eq!(
+            ids(PromptQuery { category: Some("development/review".to_string()), ..Default::default() }),
+            vec!["review", "security"]
+        );
+        assert!(ids(PromptQuery { category: Some("develop".to_string()), ..Default::default() }).is_empty());
+        assert_eq!(
+            ids(PromptQuery {
+                tags: vec!["quality".to_string(), "rust".to_string()],
+                ..Default::default()
+            }),
+            vec!["review"]
+        );
+        assert_eq!(ids(PromptQuery { trigger: Some("/COMMIT".to_string()), ..Default::default() }), vec!["commit"]);
+    }
+}
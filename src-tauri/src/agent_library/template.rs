@@ -0,0 +1,596 @@
+use super::types::PromptArgument;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+// ブロックの入れ子の上限
+const MAX_DEPTH: usize = 8;
+// 展開結果の上限（バイト）。`{{#each}}` の入れ子で出力が膨らみ続けないようにする
+const MAX_OUTPUT_LEN: usize = 1 << 20;
+const BLOCK_HELPERS: &[&str] = &["if", "each"];
+// `{{#each}}` の中で使える変数
+const LOOP_VARIABLES: &[&str] = &["this", "@index", "@number"];
+
+/// 解析済みのテンプレート
+///
+/// 展開時に参照するのは渡された引数の値だけで、ファイルやプロセスにはアクセスしない
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template<'a> {
+    nodes: Vec<Node<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node<'a> {
+    Text(&'a str),
+    // 値がなければ元の文字列を残す
+    Variable { name: &'a str, text: &'a str },
+    If { name: &'a str, then: Vec<Self>, otherwise: Vec<Self> },
+    Each { name: &'a str, body: Vec<Self>, otherwise: Vec<Self> },
+}
+
+/// テンプレートの構文エラー（1始まりの行・列）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// テンプレートを解析する
+///
+/// `{{#if name}}` / `{{#each name}}` / `{{else}}` の対応が取れていない場合はエラーを返す。
+/// 閉じていない `{{` や不正なプレースホルダーは本文としてそのまま扱う
+pub fn parse(template: &str) -> Result<Template<'_>, TemplateError> {
+    parse_nodes(template)
+        .map(|nodes| Template { nodes })
+        .map_err(|(offset, _, message)| {
+            let (line, column) = position(template, offset);
+            TemplateError { line, column, message }
+        })
+}
+
+impl Template<'_> {
+    /// 引数の値でテンプレートを展開する
+    ///
+    /// `{{#if}}` は値が空、`false`、`0` のとき偽になる。`{{#each}}` は値を JSON の配列、
+    /// 改行区切り、カンマ区切りの順に解釈し、中では `{{this}}`、`{{@index}}`（0始まり）、
+    /// `{{@number}}`（1始まり）を使える
+    #[must_use] pub fn render<S: BuildHasher>(&self, values: &HashMap<String, String, S>) -> String {
+        let mut output = String::new();
+        render_nodes(&self.nodes, values, &mut Vec::new(), &mut output);
+        if output.len() > MAX_OUTPUT_LEN {
+            tracing::warn!("Rendered template exceeds {MAX_OUTPUT_LEN} bytes, output truncated");
+            let mut end = MAX_OUTPUT_LEN;
+            while !output.is_char_boundary(end) {
+                end -= 1;
+            }
+            output.truncate(end);
+        }
+        output
+    }
+}
+
+/// プロンプト本文のテンプレートを引数の値で展開する
+///
+/// 値が与えられていないプレースホルダーはそのまま残す
+#[must_use] pub fn render<S: BuildHasher>(template: &str, values: &HashMap<String, String, S>) -> String {
+    match parse(template) {
+        Ok(parsed) => parsed.render(values),
+        Err(e) => {
+            // 読み込み時に検証済みのため通常は起きない。プレースホルダーの置換だけ行う
+            tracing::warn!("Invalid template ({e}), substituting placeholders only");
+            substitute(template, values)
+        }
+    }
+}
+
+// ブロックを解釈せずに `{{name}}` だけを置換する
+fn substitute<S: BuildHasher>(template: &str, values: &HashMap<String, String, S>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after_open = &rest[start + 2..];
+
+        let Some(end) = after_open.find("}}") else {
+            // 閉じ括弧がない場合は残りをそのまま出力
+            output.push_str(&rest[start..]);
+            return output;
+        };
+
+        let key = after_open[..end].trim();
+        match values.get(key) {
+            Some(value) if is_placeholder_name(key) => output.push_str(value),
+            _ => output.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after_open[end + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// 本文中のプレースホルダー
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placeholder<'a> {
+    pub name: &'a str,
+    // `{{ name }}` の括弧を含む元の文字列
+    pub text: &'a str,
+}
+
+/// テンプレートの構文エラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError<'a> {
+    pub text: &'a str,
+    pub message: String,
+}
+
+/// 本文中のプレースホルダー（ブロックの条件を含む）を出現順に返す
+///
+/// `{{#each}}` の中の `{{this}}` などのループ変数は含めない
+#[must_use] pub fn placeholders(template: &str) -> Vec<Placeholder<'_>> {
+    let mut blocks: Vec<&str> = Vec::new();
+
+    tags(template)
+        .into_iter()
+        .filter_map(|tag| {
+            let in_loop = blocks.contains(&"each");
+            let name = match classify(tag.inner) {
+                TagKind::Variable(name) => name,
+                TagKind::Open(helper, name) => {
+                    blocks.push(helper);
+                    name
+                }
+                TagKind::Close(_) => {
+                    blocks.pop();
+                    return None;
+                }
+                TagKind::Else | TagKind::Literal => return None,
+            };
+            let is_loop_variable = in_loop && LOOP_VARIABLES.contains(&name);
+            (is_placeholder_name(name) && !is_loop_variable).then_some(Placeholder { name, text: tag.text })
+        })
+        .collect()
+}
+
+/// 閉じられていない `{{` や不正なプレースホルダー名、ブロックの対応の誤りを返す
+#[must_use] pub fn syntax_errors(template: &str) -> Vec<SyntaxError<'_>> {
+    let mut errors: Vec<SyntaxError<'_>> = tags(template)
+        .into_iter()
+        .filter_map(|tag| match (tag.inner, classify(tag.inner)) {
+            (None, _) => Some(SyntaxError {
+                text: tag.text,
+                message: "Unclosed '{{' in template".to_string(),
+            }),
+            (Some(_), TagKind::Literal) => Some(SyntaxError {
+                text: tag.text,
+                message: format!("Invalid placeholder '{}'", tag.text),
+            }),
+            _ => None,
+        })
+        .collect();
+
+    if let Err((_, text, message)) = parse_nodes(template) {
+        errors.push(SyntaxError { text, message });
+    }
+    errors
+}
+
+// `{{...}}` の位置と中身
+struct RawTag<'a> {
+    start: usize,
+    // 括弧を含む元の文字列
+    text: &'a str,
+    // 括弧の中身。閉じていない場合は None
+    inner: Option<&'a str>,
+}
+
+enum TagKind<'a> {
+    Variable(&'a str),
+    // `{{#helper name}}`
+    Open(&'a str, &'a str),
+    // `{{/helper}}`
+    Close(&'a str),
+    Else,
+    // インクルード、不正なプレースホルダー、閉じていないタグ
+    Literal,
+}
+
+fn classify(inner: Option<&str>) -> TagKind<'_> {
+    let Some(inner) = inner.map(str::trim) else {
+        return TagKind::Literal;
+    };
+
+    if let Some(rest) = inner.strip_prefix('#') {
+        let (helper, name) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        TagKind::Open(helper, name.trim())
+    } else if let Some(helper) = inner.strip_prefix('/') {
+        TagKind::Close(helper.trim())
+    } else if inner == "else" {
+        TagKind::Else
+    } else if is_variable_name(inner) {
+        TagKind::Variable(inner)
+    } else {
+        TagKind::Literal
+    }
+}
+
+// 解析中のブロック
+struct Frame<'a> {
+    helper: &'a str,
+    name: &'a str,
+    text: &'a str,
+    start: usize,
+    nodes: Vec<Node<'a>>,
+    // `{{else}}` 以降
+    otherwise: Option<Vec<Node<'a>>>,
+}
+
+// 構文木を組み立てる。エラーは (位置, タグの文字列, メッセージ)
+fn parse_nodes(template: &str) -> Result<Vec<Node<'_>>, (usize, &str, String)> {
+    let mut root = Vec::new();
+    let mut stack: Vec<Frame<'_>> = Vec::new();
+    let mut offset = 0;
+
+    for tag in tags(template) {
+        let end = tag.start + tag.text.len();
+        let kind = classify(tag.inner);
+        let is_block_tag = matches!(kind, TagKind::Open(..) | TagKind::Close(_) | TagKind::Else);
+
+        // ブロックのタグだけの行は行ごと取り除く
+        let line_start = template[..tag.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = template[end..].find('\n').map_or(template.len(), |i| end + i + 1);
+        let standalone = is_block_tag
+            && line_start >= offset
+            && template[line_start..tag.start].trim().is_empty()
+            && template[end..line_end].trim().is_empty();
+        let (text_end, next_offset) = if standalone { (line_start, line_end) } else { (tag.start, end) };
+
+        push_text(target(&mut root, &mut stack), &template[offset..text_end]);
+        offset = next_offset;
+
+        let error = |message: String| (tag.start, tag.text, message);
+        match kind {
+            TagKind::Variable(name) => target(&mut root, &mut stack).push(Node::Variable { name, text: tag.text }),
+            TagKind::Literal => push_text(target(&mut root, &mut stack), tag.text),
+            TagKind::Open(helper, name) => {
+                if !BLOCK_HELPERS.contains(&helper) {
+                    return Err(error(format!("Unknown block helper '#{helper}'")));
+                }
+                if !is_variable_name(name) {
+                    return Err(error(format!("'{{{{#{helper}}}}}' requires an argument name")));
+                }
+                if stack.len() >= MAX_DEPTH {
+                    return Err(error(format!("Blocks are nested too deeply (limit {MAX_DEPTH})")));
+                }
+                stack.push(Frame {
+                    helper,
+                    name,
+                    text: tag.text,
+                    start: tag.start,
+                    nodes: Vec::new(),
+                    otherwise: None,
+                });
+            }
+            TagKind::Else => {
+                let Some(frame) = stack.last_mut() else {
+                    return Err(error("'{{else}}' outside of a block".to_string()));
+                };
+                if frame.otherwise.is_some() {
+                    return Err(error(format!("Duplicate '{{{{else}}}}' in '{}'", frame.text)));
+                }
+                frame.otherwise = Some(Vec::new());
+            }
+            TagKind::Close(helper) => {
+                let Some(frame) = stack.pop() else {
+                    return Err(error(format!("Unexpected '{}' without an opening block", tag.text)));
+                };
+                if frame.helper != helper {
+                    let (line, _) = position(template, frame.start);
+                    return Err(error(format!(
+                        "'{}' does not match '{}' opened at line {line}",
+                        tag.text, frame.text
+                    )));
+                }
+                let otherwise = frame.otherwise.unwrap_or_default();
+                let node = if helper == "if" {
+                    Node::If { name: frame.name, then: frame.nodes, otherwise }
+                } else {
+                    Node::Each { name: frame.name, body: frame.nodes, otherwise }
+                };
+                target(&mut root, &mut stack).push(node);
+            }
+        }
+    }
+
+    if let Some(frame) = stack.last() {
+        return Err((
+            frame.start,
+            frame.text,
+            format!("Unclosed '{}' (missing '{{{{/{}}}}}')", frame.text, frame.helper),
+        ));
+    }
+    push_text(&mut root, &template[offset..]);
+    Ok(root)
+}
+
+// 現在のブロック（なければ最上位）の追加先
+fn target<'s, 'a>(root: &'s mut Vec<Node<'a>>, stack: &'s mut [Frame<'a>]) -> &'s mut Vec<Node<'a>> {
+    match stack.last_mut() {
+        Some(frame) => frame.otherwise.as_mut().unwrap_or(&mut frame.nodes),
+        None => root,
+    }
+}
+
+fn push_text<'a>(nodes: &mut Vec<Node<'a>>, text: &'a str) {
+    if !text.is_empty() {
+        nodes.push(Node::Text(text));
+    }
+}
+
+// `{{#each}}` の現在の要素
+struct LoopItem {
+    item: String,
+    index: usize,
+}
+
+fn render_nodes<S: BuildHasher>(
+    nodes: &[Node<'_>],
+    values: &HashMap<String, String, S>,
+    scope: &mut Vec<LoopItem>,
+    output: &mut String,
+) {
+    for node in nodes {
+        if output.len() > MAX_OUTPUT_LEN {
+            return;
+        }
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Variable { name, text } => match lookup(name, values, scope) {
+                Some(value) => output.push_str(&value),
+                None => output.push_str(text),
+            },
+            Node::If { name, then, otherwise } => {
+                let branch = if lookup(name, values, scope).is_some_and(|v| is_truthy(&v)) { then } else { otherwise };
+                render_nodes(branch, values, scope, output);
+            }
+            Node::Each { name, body, otherwise } => {
+                let items = lookup(name, values, scope).map(|v| list_items(&v)).unwrap_or_default();
+                if items.is_empty() {
+                    render_nodes(otherwise, values, scope, output);
+                }
+                for (index, item) in items.into_iter().enumerate() {
+                    scope.push(LoopItem { item, index });
+                    render_nodes(body, values, scope, output);
+                    scope.pop();
+                }
+            }
+        }
+    }
+}
+
+fn lookup<S: BuildHasher>(name: &str, values: &HashMap<String, String, S>, scope: &[LoopItem]) -> Option<String> {
+    if let Some(current) = scope.last() {
+        match name {
+            "this" => return Some(current.item.clone()),
+            "@index" => return Some(current.index.to_string()),
+            "@number" => return Some((current.index + 1).to_string()),
+            _ => {}
+        }
+    }
+    values.get(name).cloned()
+}
+
+fn is_truthy(value: &str) -> bool {
+    let value = value.trim();
+    !value.is_empty() && value != "0" && !value.eq_ignore_ascii_case("false")
+}
+
+// `{{#each}}` に渡された値を要素に分ける
+fn list_items(value: &str) -> Vec<String> {
+    let trimmed = value.trim();
+    if trimmed.starts_with('[') {
+        if let Ok(serde_json::Value::Array(items)) = serde_json::from_str(trimmed) {
+            return items
+                .into_iter()
+                .map(|item| match item {
+                    serde_json::Value::String(s) => s,
+                    other => other.to_string(),
+                })
+                .collect();
+        }
+    }
+
+    let separator = if trimmed.contains('\n') { '\n' } else { ',' };
+    trimmed
+        .split(separator)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+// オフセットの位置（1始まりの行・列）
+fn position(template: &str, offset: usize) -> (usize, usize) {
+    let before = &template[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
+
+// `{{...}}` を出現順に返す
+fn tags(template: &str) -> Vec<RawTag<'_>> {
+    let mut tags = Vec::new();
+    let mut offset = 0;
+
+    while let Some(start) = template[offset..].find("{{") {
+        let start = offset + start;
+        let after_open = &template[start + 2..];
+        let Some(end) = after_open.find("}}") else {
+            let line_end = template[start..].find('\n').map_or(template.len(), |i| start + i);
+            tags.push(RawTag { start, text: &template[start..line_end], inner: None });
+            break;
+        };
+        tags.push(RawTag {
+            start,
+            text: &template[start..start + 2 + end + 2],
+            inner: Some(&after_open[..end]),
+        });
+        offset = start + 2 + end + 2;
+    }
+
+    tags
+}
+
+/// 宣言された引数と渡された値から差し込み用の値を組み立てる
+///
+/// 必須引数が不足している場合は不足している引数名の一覧を返す
+pub fn resolve_arguments<S: BuildHasher + Clone>(
+    declared: &[PromptArgument],
+    provided: &HashMap<String, String, S>,
+) -> Result<HashMap<String, String, S>, Vec<String>> {
+    let mut values = provided.clone();
+    let mut missing = Vec::new();
+
+    for argument in declared {
+        if values.contains_key(&argument.name) {
+            continue;
+        }
+        if let Some(default) = &argument.default {
+            values.insert(argument.name.clone(), default.clone());
+        } else if argument.required {
+            missing.push(argument.name.clone());
+        }
+    }
+
+    if missing.is_empty() {
+        Ok(values)
+    } else {
+        Err(missing)
+    }
+}
+
+fn is_placeholder_name(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+fn is_variable_name(key: &str) -> bool {
+    is_placeholder_name(key) || LOOP_VARIABLES.contains(&key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_substitutes_placeholders() {
+        let rendered = render(
+            "Review {{ file }} written in {{language}}.",
+            &values(&[("file", "main.rs"), ("language", "Rust")]),
+        );
+        assert_eq!(rendered, "Review main.rs written in Rust.");
+    }
+
+    #[test]
+    fn test_render_keeps_unknown_placeholders() {
+        let rendered = render("Hello {{name}} and {{other}} {{", &values(&[("name", "world")]));
+        assert_eq!(rendered, "Hello world and {{other}} {{");
+    }
+
+    #[test]
+    fn test_resolve_arguments_applies_defaults_and_reports_missing() {
+        let declared = vec![
+            PromptArgument {
+                name: "file".to_string(),
+                required: true,
+                ..Default::default()
+            },
+            PromptArgument {
+                name: "language".to_string(),
+                default: Some("Rust".to_string()),
+                ..Default::default()
+            },
+            PromptArgument {
+                name: "focus".to_string(),
+                required: true,
+                ..Default::default()
+            },
+        ];
+
+        let missing = resolve_arguments(&declared, &values(&[])).unwrap_err();
+        assert_eq!(missing, vec!["file".to_string(), "focus".to_string()]);
+
+        let resolved = resolve_arguments(&declared, &values(&[("file", "a.rs"), ("focus", "perf")])).unwrap();
+        assert_eq!(resolved.get("language").map(String::as_str), Some("Rust"));
+        assert_eq!(resolved.get("file").map(String::as_str), Some("a.rs"));
+    }
+
+    #[test]
+    fn test_placeholders_and_syntax_errors() {
+        let template = "Review {{file}} and {{ focus }}.\n{{not valid}} {{}}\nTrailing {{ diff\nnext line";
+
+        let names: Vec<_> = placeholders(template).iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["file", "focus"]);
+        assert_eq!(placeholders(template)[1].text, "{{ focus }}");
+
+        let errors = syntax_errors(template);
+        let texts: Vec<_> = errors.iter().map(|e| e.text).collect();
+        assert_eq!(texts, vec!["{{not valid}}", "{{}}", "{{ diff"]);
+        assert_eq!(errors[2].message, "Unclosed '{{' in template");
+    }
+
+    #[test]
+    fn test_render_blocks() {
+        let template = "Review {{file}}.\n{{#if language}}\nLanguage: {{language}}\n{{else}}\nDetect the language.\n{{/if}}\n{{#each focus}}\n{{@number}}. {{this}}\n{{else}}\nNo focus.\n{{/each}}\nDone.";
+        let parsed = parse(template).unwrap();
+
+        let rendered = parsed.render(&values(&[("file", "a.rs"), ("language", "Rust"), ("focus", "perf, safety")]));
+        assert_eq!(rendered, "Review a.rs.\nLanguage: Rust\n1. perf\n2. safety\nDone.");
+
+        let rendered = parsed.render(&values(&[("file", "a.rs"), ("language", "false"), ("focus", r#"["x, y"]"#)]));
+        assert_eq!(rendered, "Review a.rs.\nDetect the language.\n1. x, y\nDone.");
+
+        let rendered = parsed.render(&values(&[("file", "a.rs")]));
+        assert_eq!(rendered, "Review a.rs.\nDetect the language.\nNo focus.\nDone.");
+    }
+
+    #[test]
+    fn test_parse_block_errors() {
+        let error = parse("# Review\n{{#if language}}\nUse {{language}}.\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        assert!(error.message.contains("missing '{{/if}}'"));
+
+        let error = parse("{{#each files}}\n{{this}}\n  {{/if}}").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 3));
+        assert_eq!(error.message, "'{{/if}}' does not match '{{#each files}}' opened at line 1");
+
+        assert!(parse("{{#unless x}}{{/unless}}").unwrap_err().message.contains("Unknown block helper"));
+        assert!(parse("{{else}}").is_err());
+        assert!(parse("{{> shared/style.md}} {{ diff").is_ok());
+
+        let names: Vec<_> = placeholders("{{#each files}}{{this}} {{@index}} {{focus}}{{/each}} {{this}}")
+            .iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, vec!["files", "focus", "this"]);
+        assert_eq!(syntax_errors("{{#if x}}")[0].text, "{{#if x}}");
+    }
+}
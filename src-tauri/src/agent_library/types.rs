@@ -1,19 +1,159 @@
+use super::lint::LintConfig;
+use super::markdown::MarkdownSummary;
+use super::taxonomy::Taxonomy;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AgentIndex {
+    // ライブラリ全体のメタデータ（MCPの serverInfo に使用）
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub version: Option<String>,
+    // 継承元ライブラリ（絶対パスまたはリポジトリからの相対パス）
+    pub extends: Option<String>,
+    // プロンプトファイルを自動登録するglobパターン（.agent_library からの相対パス）
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
     pub mcp_endpoints: Vec<McpEndpoint>,
+    // リントルールの設定
+    #[serde(default)]
+    pub lint: LintConfig,
+    // ライブラリ全体で公開するファイル（MCP のリソース）
+    #[serde(default)]
+    pub resources: Vec<ResourceEntry>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct McpEndpoint {
     pub id: String,
+    // front matter で指定する場合は省略可能
+    #[serde(default)]
     pub label: String,
+    #[serde(default)]
     pub description: String,
     pub prompt_file: String,
     pub trigger: Option<String>,
+    // `/` 区切りで階層にできる（例: `development/review`）
     pub category: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub arguments: Vec<PromptArgument>,
+    // ロケールごとのプロンプトファイル（例: `ja: prompts/review.ja.md`）
+    #[serde(default)]
+    pub locales: BTreeMap<String, String>,
+    // プロンプトに添付するファイル（MCP のリソースとして公開）
+    #[serde(default)]
+    pub attachments: Vec<ResourceEntry>,
+    // 以前のID（名前を変えても古いIDで呼び出せる）
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub deprecated: Option<Deprecation>,
+    // `include` パターンで自動登録されたエンドポイント
+    #[serde(default, skip_deserializing)]
+    pub discovered: bool,
+}
+
+// 非推奨のエンドポイント
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deprecation {
+    // 代わりに使うエンドポイントのID
+    pub replaced_by: Option<String>,
+    pub message: Option<String>,
+}
+
+impl Deprecation {
+    /// 説明に添える非推奨の案内
+    #[must_use] pub fn notice(&self) -> String {
+        let replacement = self
+            .replaced_by
+            .as_ref()
+            .map(|id| format!(": use '{id}' instead"))
+            .unwrap_or_default();
+        let message = self
+            .message
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .map(|m| format!(". {m}"))
+            .unwrap_or_default();
+        format!("Deprecated{replacement}{message}")
+    }
+}
+
+// プロンプト本文の `{{name}}` に差し込む引数の定義
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptArgument {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+    pub default: Option<String>,
+}
+
+// `attachments:` / `resources:` のエントリ。パスだけの文字列でも指定できる
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "ResourceSpec")]
+pub struct ResourceEntry {
+    // .agent_library からの相対パス
+    pub path: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    // 省略時はファイルから判定する
+    pub mime_type: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ResourceSpec {
+    Path(String),
+    Detailed {
+        path: String,
+        name: Option<String>,
+        description: Option<String>,
+        #[serde(alias = "mimeType")]
+        mime_type: Option<String>,
+    },
+}
+
+impl From<ResourceSpec> for ResourceEntry {
+    fn from(spec: ResourceSpec) -> Self {
+        match spec {
+            ResourceSpec::Path(path) => Self {
+                path,
+                ..Default::default()
+            },
+            ResourceSpec::Detailed {
+                path,
+                name,
+                description,
+                mime_type,
+            } => Self {
+                path,
+                name,
+                description,
+                mime_type,
+            },
+        }
+    }
+}
+
+// MCP のリソースとして公開するファイル
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryResource {
+    pub uri: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub mime_type: String,
+    #[serde(skip)]
+    pub file_path: PathBuf,
+    // 添付ファイルの場合は添付先のプロンプト
+    pub prompt_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,16 +162,91 @@ pub struct AgentLibrary {
     #[serde(skip)]
     pub base_path: PathBuf,
     pub prompts: Vec<Prompt>,
+    // `resources:` で公開するファイル
+    #[serde(default)]
+    pub resources: Vec<LibraryResource>,
+    // タグ・カテゴリ・トリガーの索引
+    #[serde(default)]
+    pub taxonomy: Taxonomy,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl AgentLibrary {
+    /// ID または別名でプロンプトを探す（ID が優先される）
+    #[must_use] pub fn find_prompt(&self, name: &str) -> Option<&Prompt> {
+        self.prompts
+            .iter()
+            .find(|p| p.id == name)
+            .or_else(|| self.prompts.iter().find(|p| p.aliases.iter().any(|a| a == name)))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Prompt {
     pub id: String,
     pub title: String,
     pub description: String,
+    // front matter を除いた本文
     pub content: String,
+    // front matter を含むファイルの内容（編集用）
+    #[serde(default)]
+    pub raw_content: String,
+    // `raw_content` の SHA-256（保存時の競合の検出に使う）
+    #[serde(default)]
+    pub content_hash: String,
     #[serde(skip)]
     pub file_path: PathBuf,
+    // プロンプトを定義している .agent_library（extends で継承した場合は継承元）
+    #[serde(default)]
+    pub source_library: PathBuf,
+    // インクルードで展開したファイル
+    #[serde(skip)]
+    pub included_files: Vec<PathBuf>,
+    // ロール区切りで分割したメッセージ（順序どおり）
+    #[serde(default)]
+    pub messages: Vec<PromptMessage>,
+    #[serde(default)]
+    pub arguments: Vec<PromptArgument>,
+    pub category: Option<String>,
+    pub trigger: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // ロケール別のバリアント（ロケール順）
+    #[serde(default)]
+    pub variants: Vec<PromptVariant>,
+    // 本文（インクルード展開後）の構造
+    #[serde(default)]
+    pub summary: MarkdownSummary,
+    // `attachments:` で添付したファイル
+    #[serde(default)]
+    pub attachments: Vec<LibraryResource>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub deprecated: Option<Deprecation>,
+}
+
+// ロケール別のプロンプト。引数などのメタデータは基本のプロンプトと共通
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromptVariant {
+    pub locale: String,
+    pub title: String,
+    pub description: String,
+    pub content: String,
+    #[serde(default)]
+    pub raw_content: String,
+    #[serde(skip)]
+    pub file_path: PathBuf,
+    #[serde(skip)]
+    pub included_files: Vec<PathBuf>,
+    #[serde(default)]
+    pub messages: Vec<PromptMessage>,
+    #[serde(default)]
+    pub summary: MarkdownSummary,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptMessage {
+    pub role: String,
+    pub content: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,4 +256,4 @@ pub struct Repository {
     #[serde(skip)]
     pub path: PathBuf,
     pub agent_library: Option<AgentLibrary>,
-}
\ No newline at end of file
+}
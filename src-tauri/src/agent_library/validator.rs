@@ -0,0 +1,703 @@
+use super::{discovery, front_matter, include, locale, markdown, messages, resources, template};
+use super::parser::AgentLibraryParser;
+use super::types::AgentIndex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+// agent_index.yml で認識するキー
+const INDEX_KEYS: &[&str] = &[
+    "name",
+    "description",
+    "version",
+    "extends",
+    "include",
+    "exclude",
+    "mcp_endpoints",
+    "lint",
+    "resources",
+];
+const ENDPOINT_KEYS: &[&str] = &[
+    "id",
+    "label",
+    "description",
+    "prompt_file",
+    "trigger",
+    "category",
+    "tags",
+    "arguments",
+    "locales",
+    "attachments",
+    "aliases",
+    "deprecated",
+];
+
+/// 診断の重要度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// 検証で見つかった問題（問題パネル表示用）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: String,
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(severity: Severity, code: &str, file: Option<&Path>, message: String) -> Self {
+        Self {
+            severity,
+            code: code.to_string(),
+            file: file.map(Path::to_path_buf),
+            line: None,
+            column: None,
+            message,
+        }
+    }
+
+    pub(crate) fn error(code: &str, file: Option<&Path>, message: String) -> Self {
+        Self::new(Severity::Error, code, file, message)
+    }
+
+    pub(crate) fn warning(code: &str, file: Option<&Path>, message: String) -> Self {
+        Self::new(Severity::Warning, code, file, message)
+    }
+
+    pub(crate) const fn at(mut self, position: Option<(usize, usize)>) -> Self {
+        if let Some((line, column)) = position {
+            self.line = Some(line);
+            self.column = Some(column);
+        }
+        self
+    }
+}
+
+/// `.agent_library` ディレクトリを検証して診断の一覧を返す
+#[must_use] pub fn validate(agent_lib_path: &Path) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    if !agent_lib_path.is_dir() {
+        diagnostics.push(Diagnostic::error(
+            "missing-directory",
+            Some(agent_lib_path),
+            format!(".agent_library directory not found: {}", agent_lib_path.display()),
+        ));
+        return diagnostics;
+    }
+
+    let index_path = agent_lib_path.join("agent_index.yml");
+    let content = match fs::read_to_string(&index_path) {
+        Ok(content) => content,
+        Err(e) => {
+            diagnostics.push(Diagnostic::error(
+                "missing-index",
+                Some(&index_path),
+                format!("Failed to read agent_index.yml: {e}"),
+            ));
+            return diagnostics;
+        }
+    };
+
+    // 構文エラーとスキーマエラーを区別するため、まず汎用のYAMLとして読む
+    let value: serde_yaml::Value = match serde_yaml::from_str(&content) {
+        Ok(value) => value,
+        Err(e) => {
+            diagnostics.push(
+                Diagnostic::error("yaml-syntax", Some(&index_path), e.to_string())
+                    .at(e.location().map(|l| (l.line(), l.column()))),
+            );
+            return diagnostics;
+        }
+    };
+
+    check_unknown_keys(&value, &content, &index_path, &mut diagnostics);
+
+    let index: AgentIndex = match serde_yaml::from_str(&content) {
+        Ok(index) => index,
+        Err(e) => {
+            diagnostics.push(
+                Diagnostic::error("invalid-index", Some(&index_path), e.to_string())
+                    .at(e.location().map(|l| (l.line(), l.column()))),
+            );
+            return diagnostics;
+        }
+    };
+
+    // メタデータの問題は読み込みを止めず、診断として報告する
+    if let Err(e) = AgentLibraryParser::validate_index_metadata(&index) {
+        diagnostics.push(Diagnostic::warning("invalid-metadata", Some(&index_path), e.to_string()));
+    }
+
+    if let Some(extends) = &index.extends {
+        if let Err(e) = AgentLibraryParser::resolve_extends(extends, agent_lib_path) {
+            diagnostics.push(
+                Diagnostic::error("invalid-extends", Some(&index_path), e.to_string())
+                    .at(find_key(content.as_str(), "extends", 0)),
+            );
+        }
+    }
+
+    // 自動登録されたエンドポイントも明示エントリと同様に検証する
+    let mut index = index;
+    match discovery::discover_endpoints(agent_lib_path, &index) {
+        Ok(discovered) => index.mcp_endpoints.extend(discovered),
+        Err(e) => {
+            diagnostics.push(Diagnostic::error("invalid-discovery", Some(&index_path), format!("{e:#}")));
+        }
+    }
+
+    let mut referenced_files = check_endpoints(agent_lib_path, &index, &content, &index_path, &mut diagnostics);
+    check_resources(agent_lib_path, &index, &content, &index_path, &mut referenced_files, &mut diagnostics);
+    check_aliases(&index, &content, &index_path, &mut diagnostics);
+    check_orphaned_files(agent_lib_path, &index, &referenced_files, &mut diagnostics);
+
+    diagnostics
+}
+
+fn check_unknown_keys(
+    value: &serde_yaml::Value,
+    content: &str,
+    index_path: &Path,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let Some(mapping) = value.as_mapping() else {
+        return;
+    };
+
+    for key in mapping.keys().filter_map(serde_yaml::Value::as_str) {
+        if !INDEX_KEYS.contains(&key) {
+            diagnostics.push(
+                Diagnostic::warning(
+                    "unknown-key",
+                    Some(index_path),
+                    format!("Unknown key '{key}' in agent_index.yml"),
+                )
+                .at(find_key(content, key, 0)),
+            );
+        }
+    }
+
+    let endpoints = mapping
+        .get("mcp_endpoints")
+        .and_then(serde_yaml::Value::as_sequence)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for endpoint in endpoints.iter().filter_map(serde_yaml::Value::as_mapping) {
+        for key in endpoint.keys().filter_map(serde_yaml::Value::as_str) {
+            if !ENDPOINT_KEYS.contains(&key) {
+                let occurrence = seen.entry(key).or_insert(0);
+                diagnostics.push(
+                    Diagnostic::warning(
+                        "unknown-key",
+                        Some(index_path),
+                        format!("Unknown key '{key}' in mcp_endpoints entry"),
+                    )
+                    .at(find_key(content, key, *occurrence)),
+                );
+                *occurrence += 1;
+            }
+        }
+    }
+}
+
+fn check_endpoints(
+    agent_lib_path: &Path,
+    index: &AgentIndex,
+    content: &str,
+    index_path: &Path,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> HashSet<PathBuf> {
+    let mut ids = HashSet::new();
+    // インクルードされたファイルとロケール別ファイル（正規化済み）
+    let mut referenced = HashSet::new();
+
+    for (position, endpoint) in index.mcp_endpoints.iter().enumerate() {
+        if !ids.insert(endpoint.id.as_str()) {
+            diagnostics.push(
+                Diagnostic::error(
+                    "duplicate-id",
+                    Some(index_path),
+                    format!("Duplicate endpoint id '{}'", endpoint.id),
+                )
+                .at(find_key(content, "id", position)),
+            );
+        }
+
+        let prompt_path = agent_lib_path.join(&endpoint.prompt_file);
+        let Ok(raw_content) = fs::read_to_string(&prompt_path) else {
+            diagnostics.push(
+                Diagnostic::error(
+                    "missing-prompt-file",
+                    Some(index_path),
+                    format!(
+                        "Prompt file '{}' for endpoint '{}' not found",
+                        endpoint.prompt_file, endpoint.id
+                    ),
+                )
+                .at(find_key(content, "prompt_file", position)),
+            );
+            continue;
+        };
+
+        check_prompt_content(agent_lib_path, &prompt_path, &raw_content, &endpoint.id, &mut referenced, diagnostics);
+
+        for (tag, file) in &endpoint.locales {
+            if !locale::is_locale_tag(tag) {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        "invalid-locale",
+                        Some(index_path),
+                        format!("'{tag}' in endpoint '{}' is not a valid locale tag", endpoint.id),
+                    )
+                    .at(find_key(content, tag, 0)),
+                );
+            }
+            if !agent_lib_path.join(file).is_file() {
+                diagnostics.push(
+                    Diagnostic::error(
+                        "missing-prompt-file",
+                        Some(index_path),
+                        format!("Locale file '{file}' for endpoint '{}' not found", endpoint.id),
+                    )
+                    .at(find_key(content, tag, 0)),
+                );
+            }
+        }
+
+        for (_, variant_path) in locale::variant_files(agent_lib_path, endpoint) {
+            let Ok(raw_content) = fs::read_to_string(&variant_path) else {
+                continue;
+            };
+            check_prompt_content(agent_lib_path, &variant_path, &raw_content, &endpoint.id, &mut referenced, diagnostics);
+            referenced.insert(variant_path.canonicalize().unwrap_or(variant_path));
+        }
+    }
+
+    referenced
+}
+
+// 別名が他のIDや別名と重複していないか、非推奨の置き換え先が存在するかを確認する
+fn check_aliases(index: &AgentIndex, content: &str, index_path: &Path, diagnostics: &mut Vec<Diagnostic>) {
+    let ids: HashSet<&str> = index.mcp_endpoints.iter().map(|e| e.id.as_str()).collect();
+    let mut seen: HashMap<&str, &str> = HashMap::new();
+    let mut aliases_key = 0;
+    let mut deprecated_key = 0;
+
+    for endpoint in &index.mcp_endpoints {
+        let position = (!endpoint.aliases.is_empty()).then(|| {
+            aliases_key += 1;
+            find_key(content, "aliases", aliases_key - 1)
+        });
+        for alias in &endpoint.aliases {
+            let collision = if ids.contains(alias.as_str()) {
+                Some(format!("Alias '{alias}' of endpoint '{}' collides with endpoint id '{alias}'", endpoint.id))
+            } else {
+                seen.get(alias.as_str()).filter(|owner| **owner != endpoint.id).map(|owner| {
+                    format!("Alias '{alias}' of endpoint '{}' is also an alias of endpoint '{owner}'", endpoint.id)
+                })
+            };
+            if let Some(message) = collision {
+                diagnostics.push(
+                    Diagnostic::warning("alias-collision", Some(index_path), message).at(position.flatten()),
+                );
+            }
+            seen.entry(alias).or_insert(&endpoint.id);
+        }
+
+        let Some(deprecated) = &endpoint.deprecated else {
+            continue;
+        };
+        deprecated_key += 1;
+        if let Some(replaced_by) = &deprecated.replaced_by {
+            if !ids.contains(replaced_by.as_str()) || replaced_by == &endpoint.id {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        "unknown-replacement",
+                        Some(index_path),
+                        format!("Endpoint '{}' is replaced by unknown endpoint '{replaced_by}'", endpoint.id),
+                    )
+                    .at(find_key(content, "deprecated", deprecated_key - 1)),
+                );
+            }
+        }
+    }
+}
+
+// `resources:` と `attachments:` のファイルを検証し、解決したファイルを `referenced` に追加する
+fn check_resources(
+    agent_lib_path: &Path,
+    index: &AgentIndex,
+    content: &str,
+    index_path: &Path,
+    referenced: &mut HashSet<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let entries = index.resources.iter().map(|entry| (entry, None)).chain(
+        index
+            .mcp_endpoints
+            .iter()
+            .flat_map(|e| e.attachments.iter().map(move |entry| (entry, Some(e.id.as_str())))),
+    );
+
+    for (entry, endpoint_id) in entries {
+        match resources::resolve_path(agent_lib_path, &entry.path) {
+            Ok(path) => {
+                referenced.insert(path);
+            }
+            Err(e) => {
+                let message = match endpoint_id {
+                    Some(id) => format!("{e} (attachment of endpoint '{id}')"),
+                    None => e.to_string(),
+                };
+                diagnostics.push(
+                    Diagnostic::error("missing-resource", Some(index_path), message)
+                        .at(find_value(content, &entry.path)),
+                );
+            }
+        }
+    }
+}
+
+// プロンプトファイルの本文を検証し、インクルードしたファイルを `referenced` に追加する
+fn check_prompt_content(
+    agent_lib_path: &Path,
+    prompt_path: &Path,
+    raw_content: &str,
+    id: &str,
+    referenced: &mut HashSet<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    match front_matter::split_front_matter(raw_content) {
+        Ok((_, body)) if body.trim().is_empty() => {
+            diagnostics.push(Diagnostic::warning(
+                "empty-content",
+                Some(prompt_path),
+                format!("Prompt '{id}' has no content"),
+            ));
+        }
+        Ok((_, body)) => {
+            check_relative_links(prompt_path, raw_content, body, diagnostics);
+            if let Err(e) = template::parse(body) {
+                // front matter の行数だけ行番号をずらす
+                let offset = raw_content[..raw_content.len() - body.len()].matches('\n').count();
+                diagnostics.push(
+                    Diagnostic::error("invalid-template", Some(prompt_path), e.message)
+                        .at(Some((e.line + offset, e.column))),
+                );
+            }
+            match include::expand_includes(body, prompt_path, agent_lib_path) {
+                Ok(expansion) => {
+                    if let Err(e) = messages::split_messages(&expansion.content) {
+                        diagnostics.push(Diagnostic::error("invalid-message", Some(prompt_path), e.to_string()));
+                    }
+                    referenced.extend(expansion.included_files);
+                }
+                Err(e) => {
+                    diagnostics.push(Diagnostic::error("invalid-include", Some(prompt_path), e.to_string()));
+                }
+            }
+        }
+        Err(e) => {
+            let location = e
+                .downcast_ref::<serde_yaml::Error>()
+                .and_then(serde_yaml::Error::location)
+                // front matter は2行目から始まる
+                .map(|l| (l.line() + 1, l.column()));
+            diagnostics.push(
+                Diagnostic::error("invalid-front-matter", Some(prompt_path), format!("{e:#}"))
+                    .at(location),
+            );
+        }
+    }
+}
+
+// プロンプトファイルからの相対リンクの参照先が存在するか確認する
+fn check_relative_links(prompt_path: &Path, raw_content: &str, body: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(directory) = prompt_path.parent() else {
+        return;
+    };
+    // front matter の行数だけ行番号をずらす
+    let offset = raw_content[..raw_content.len() - body.len()].matches('\n').count();
+
+    for link in markdown::summarize(body).links {
+        let Some(target) = markdown::relative_link_target(&link.url) else {
+            continue;
+        };
+        if !directory.join(target).exists() {
+            diagnostics.push(
+                Diagnostic::warning(
+                    "broken-link",
+                    Some(prompt_path),
+                    format!("Link target '{}' does not exist", link.url),
+                )
+                .at(Some((link.line + offset, link.column))),
+            );
+        }
+    }
+}
+
+fn check_orphaned_files(
+    agent_lib_path: &Path,
+    index: &AgentIndex,
+    referenced_files: &HashSet<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    // インクルードされたファイルやロケール別ファイルは正規化済みのパスで比較する
+    let referenced: HashSet<PathBuf> = index
+        .mcp_endpoints
+        .iter()
+        .filter_map(|endpoint| agent_lib_path.join(&endpoint.prompt_file).canonicalize().ok())
+        .chain(referenced_files.iter().cloned())
+        .collect();
+
+    for entry in WalkDir::new(agent_lib_path)
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+    {
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension().and_then(|e| e.to_str()) != Some("md") {
+            continue;
+        }
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if !referenced.contains(&canonical) {
+            diagnostics.push(Diagnostic::warning(
+                "orphaned-file",
+                Some(path),
+                format!(
+                    "{} is not referenced by agent_index.yml",
+                    path.strip_prefix(agent_lib_path).unwrap_or(path).display()
+                ),
+            ));
+        }
+    }
+}
+
+// `key:` が n 番目に現れる位置（1始まりの行・列）を探す
+fn find_key(content: &str, key: &str, nth: usize) -> Option<(usize, usize)> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(number, line)| {
+            let trimmed = line.trim_start();
+            let item = trimmed.strip_prefix("- ").map_or(trimmed, str::trim_start);
+            let unquoted = item
+                .strip_prefix(['\'', '"'])
+                .and_then(|rest| rest.strip_prefix(key))
+                .and_then(|rest| rest.strip_prefix(['\'', '"']))
+                .or_else(|| item.strip_prefix(key));
+            unquoted
+                .filter(|rest| rest.trim_start().starts_with(':'))
+                .map(|_| (number + 1, line.len() - item.len() + 1))
+        })
+        .nth(nth)
+}
+
+// 値 `value` が最初に現れる位置（1始まりの行・列）を探す
+fn find_value(content: &str, value: &str) -> Option<(usize, usize)> {
+    content
+        .lines()
+        .enumerate()
+        .find_map(|(number, line)| line.find(value).map(|column| (number + 1, column + 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_library(dir: &Path, index: &str, files: &[(&str, &str)]) -> PathBuf {
+        let agent_lib_dir = dir.join(".agent_library");
+        fs::create_dir_all(&agent_lib_dir).unwrap();
+        fs::write(agent_lib_dir.join("agent_index.yml"), index).unwrap();
+        for (name, content) in files {
+            let path = agent_lib_dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        agent_lib_dir
+    }
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_validate_clean_library() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = "name: Test\nmcp_endpoints:\n  - id: a\n    label: A\n    description: A\n    prompt_file: a.md\n";
+        let agent_lib_dir = write_library(
+            temp_dir.path(),
+            index,
+            &[
+                ("a.md", "# A\n{{> shared/style.md}}"),
+                ("a.ja.md", "# エー\n{{> shared/style.md}}"),
+                ("shared/style.md", "Style"),
+            ],
+        );
+
+        assert!(validate(&agent_lib_dir).is_empty());
+    }
+
+    #[test]
+    fn test_validate_broken_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = "name: Test\nmcp_endpoints:\n  - id: a\n    label: A\n    description: A\n    prompt_file: prompts/a.md\n";
+        let agent_lib_dir = write_library(
+            temp_dir.path(),
+            index,
+            &[
+                ("prompts/a.md", "---\ntitle: A\n---\n# A\n\n[Guide](../docs/guide.md#usage) [Missing](missing.md) [Web](https://example.com)"),
+                ("docs/guide.md", "# Guide"),
+            ],
+        );
+
+        let diagnostics: Vec<_> = validate(&agent_lib_dir)
+            .into_iter()
+            .filter(|d| d.code == "broken-link")
+            .collect();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("missing.md"));
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (Some(6), Some(33)));
+    }
+
+    #[test]
+    fn test_validate_locale_variants() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = "name: Test\nmcp_endpoints:\n  - id: a\n    label: A\n    description: A\n    prompt_file: a.md\n    locales:\n      japanese: a-ja.md\n      en: missing.md\n";
+        let agent_lib_dir = write_library(
+            temp_dir.path(),
+            index,
+            &[("a.md", "# A"), ("a-ja.md", "<!-- role: system -->\n# エー")],
+        );
+
+        let diagnostics = validate(&agent_lib_dir);
+        let mut codes = codes(&diagnostics);
+        codes.sort_unstable();
+        assert_eq!(codes, vec!["invalid-locale", "invalid-message", "missing-prompt-file"]);
+        let missing = diagnostics.iter().find(|d| d.code == "missing-prompt-file").unwrap();
+        assert_eq!(missing.line, Some(9));
+    }
+
+    #[test]
+    fn test_validate_template_blocks() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = "name: Test\nmcp_endpoints:\n  - id: a\n    label: A\n    description: A\n    prompt_file: a.md\n";
+        let agent_lib_dir = write_library(
+            temp_dir.path(),
+            index,
+            &[("a.md", "---\ntitle: A\n---\n{{#each files}}\n- {{this}}\n{{/if}}\n")],
+        );
+
+        let diagnostics = validate(&agent_lib_dir);
+        assert_eq!(codes(&diagnostics), vec!["invalid-template"]);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (Some(6), Some(1)));
+    }
+
+    #[test]
+    fn test_validate_aliases() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = "name: Test\nmcp_endpoints:\n  - id: a\n    label: A\n    description: A\n    prompt_file: a.md\n    aliases: [old_a, b]\n    deprecated:\n      replaced_by: c\n  - id: b\n    label: B\n    description: B\n    prompt_file: b.md\n    aliases: [old_a]\n";
+        let agent_lib_dir = write_library(temp_dir.path(), index, &[("a.md", "# A"), ("b.md", "# B")]);
+
+        let diagnostics = validate(&agent_lib_dir);
+        assert_eq!(codes(&diagnostics), vec!["alias-collision", "unknown-replacement", "alias-collision"]);
+        assert!(diagnostics[0].message.contains("endpoint id 'b'"));
+        assert_eq!(diagnostics[0].line, Some(7));
+        assert_eq!(diagnostics[1].line, Some(8));
+        assert!(diagnostics[2].message.contains("also an alias of endpoint 'a'"));
+        assert_eq!(diagnostics[2].line, Some(14));
+    }
+
+    #[test]
+    fn test_validate_resources() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = "name: Test\nresources:\n  - path: schemas/review.json\n  - logo.png\nmcp_endpoints:\n  - id: a\n    label: A\n    description: A\n    prompt_file: a.md\n    attachments:\n      - docs/checklist.md\n";
+        let agent_lib_dir = write_library(
+            temp_dir.path(),
+            index,
+            &[("a.md", "# A"), ("docs/checklist.md", "- [ ] tests"), ("schemas/review.json", "{}")],
+        );
+
+        let diagnostics = validate(&agent_lib_dir);
+        assert_eq!(codes(&diagnostics), vec!["missing-resource"]);
+        assert!(diagnostics[0].message.contains("logo.png"));
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (Some(4), Some(5)));
+    }
+
+    #[test]
+    fn test_validate_yaml_syntax_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let agent_lib_dir = write_library(temp_dir.path(), "name: Test\nmcp_endpoints: [\n", &[]);
+
+        let diagnostics = validate(&agent_lib_dir);
+        assert_eq!(codes(&diagnostics), vec!["yaml-syntax"]);
+        assert!(diagnostics[0].line.is_some());
+    }
+
+    #[test]
+    fn test_validate_reports_library_problems() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = r"name: Test
+owner: someone
+mcp_endpoints:
+  - id: a
+    label: A
+    description: A
+    prompt_file: a.md
+  - id: a
+    label: A again
+    description: A
+    prompt_file: empty.md
+    priority: high
+  - id: b
+    label: B
+    description: B
+    prompt_file: missing.md
+  - id: c
+    label: C
+    description: C
+    prompt_file: c.md
+";
+        let agent_lib_dir = write_library(
+            temp_dir.path(),
+            index,
+            &[
+                ("a.md", "# A"),
+                ("empty.md", "---\ntitle: Empty\n---\n"),
+                ("c.md", "{{> shared/missing.md}}"),
+                ("notes/orphan.md", "# Orphan"),
+            ],
+        );
+
+        let diagnostics = validate(&agent_lib_dir);
+        let codes = codes(&diagnostics);
+        assert_eq!(codes.iter().filter(|c| **c == "unknown-key").count(), 2);
+        assert!(codes.contains(&"duplicate-id"));
+        assert!(codes.contains(&"empty-content"));
+        assert!(codes.contains(&"missing-prompt-file"));
+        assert!(codes.contains(&"orphaned-file"));
+        assert!(codes.contains(&"invalid-include"));
+
+        let owner = diagnostics.iter().find(|d| d.message.contains("'owner'")).unwrap();
+        assert_eq!((owner.line, owner.column), (Some(2), Some(1)));
+
+        let duplicate = diagnostics.iter().find(|d| d.code == "duplicate-id").unwrap();
+        assert_eq!(duplicate.line, Some(8));
+        assert_eq!(duplicate.severity, Severity::Error);
+
+        let missing = diagnostics.iter().find(|d| d.code == "missing-prompt-file").unwrap();
+        assert_eq!((missing.line, missing.column), (Some(16), Some(5)));
+    }
+}
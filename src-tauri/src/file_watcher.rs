@@ -93,6 +93,8 @@ impl FileWatcherManager {
         tokio::spawn(async move {
             while let Some(event) = rx.recv().await {
                 if let Some(change_event) = Self::process_notify_event(repo_id_clone.clone(), event) {
+                    // 変更されたファイルを参照しているライブラリのキャッシュを破棄
+                    crate::agent_library::AgentLibraryParser::invalidate(Path::new(&change_event.file_path));
                     if let Err(e) = sender.send(change_event) {
                         eprintln!("Failed to forward file change event: {e}");
                         break;
@@ -70,7 +70,261 @@ async fn validate_agent_library(path: String) -> Result<bool, String> {
 }
 
 #[tauri::command]
-async fn find_repositories(search_paths: Vec<String>) -> Result<Vec<String>, String> {
+async fn get_agent_library_diagnostics(path: String) -> Result<Vec<agent_library::Diagnostic>, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&path)?;
+    
+    let repo_path = std::path::Path::new(&path);
+    Ok(agent_library::AgentLibraryParser::validate(repo_path))
+}
+
+#[tauri::command]
+async fn lint_agent_library(path: String) -> Result<Vec<agent_library::Diagnostic>, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&path)?;
+    
+    agent_library::AgentLibraryParser::lint(Path::new(&path))
+        .map_err(|e| format!("Failed to lint agent library: {e}"))
+}
+
+#[tauri::command]
+async fn get_prompt_summary(
+    repository_path: String,
+    prompt_id: String,
+    locale: Option<String>,
+) -> Result<agent_library::markdown::MarkdownSummary, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    
+    let library = agent_library::AgentLibraryParser::parse(Path::new(&repository_path))
+        .map_err(|e| format!("Failed to parse agent library: {e}"))?;
+    let prompt = library.prompts.into_iter()
+        .find(|p| p.id == prompt_id)
+        .ok_or_else(|| format!("Prompt '{prompt_id}' not found"))?;
+    
+    let variant = locale.and_then(|l| agent_library::locale::select(&prompt.variants, &l).cloned());
+    Ok(variant.map_or(prompt.summary, |v| v.summary))
+}
+
+#[tauri::command]
+async fn get_library_taxonomy(repository_path: String) -> Result<agent_library::Taxonomy, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    
+    let library = agent_library::AgentLibraryParser::parse(Path::new(&repository_path))
+        .map_err(|e| format!("Failed to parse agent library: {e}"))?;
+    Ok(library.taxonomy)
+}
+
+#[tauri::command]
+async fn query_prompts(
+    repository_path: String,
+    query: agent_library::PromptQuery,
+) -> Result<Vec<agent_library::Prompt>, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    
+    let library = agent_library::AgentLibraryParser::parse(Path::new(&repository_path))
+        .map_err(|e| format!("Failed to parse agent library: {e}"))?;
+    Ok(agent_library::taxonomy::query(&library, &query).into_iter().cloned().collect())
+}
+
+#[tauri::command]
+async fn export_agent_library(
+    repository_path: String,
+    output_path: String,
+    format: Option<agent_library::bundle::BundleFormat>,
+) -> Result<agent_library::bundle::BundleManifest, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    validate_path_security(&output_path)?;
+    
+    agent_library::bundle::export(Path::new(&repository_path), Path::new(&output_path), format)
+        .map_err(|e| format!("Failed to export agent library: {e:#}"))
+}
+
+#[tauri::command]
+async fn inspect_library_bundle(
+    bundle_path: String,
+    repository_path: String,
+) -> Result<agent_library::bundle::ImportPreview, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&bundle_path)?;
+    validate_path_security(&repository_path)?;
+    
+    agent_library::bundle::inspect(Path::new(&bundle_path), Path::new(&repository_path))
+        .map_err(|e| format!("Failed to read bundle: {e:#}"))
+}
+
+#[tauri::command]
+async fn import_agent_library(
+    bundle_path: String,
+    repository_path: String,
+    overwrite: Option<bool>,
+) -> Result<agent_library::bundle::ImportReport, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&bundle_path)?;
+    validate_path_security(&repository_path)?;
+    
+    agent_library::bundle::import(
+        Path::new(&bundle_path),
+        Path::new(&repository_path),
+        overwrite.unwrap_or(false),
+    )
+    .map_err(|e| format!("Failed to import agent library: {e:#}"))
+}
+
+#[tauri::command]
+async fn import_prompt_files(
+    repository_path: String,
+    formats: Option<Vec<agent_library::importer::ToolFormat>>,
+    dry_run: Option<bool>,
+) -> Result<agent_library::importer::ImportSummary, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    
+    agent_library::importer::import_prompts(
+        Path::new(&repository_path),
+        &formats.unwrap_or_default(),
+        dry_run.unwrap_or(false),
+    )
+    .map_err(|e| format!("Failed to import prompt files: {e:#}"))
+}
+
+#[tauri::command]
+async fn export_prompt_files(
+    repository_path: String,
+    formats: Option<Vec<agent_library::importer::ToolFormat>>,
+    dry_run: Option<bool>,
+) -> Result<agent_library::exporter::ExportSummary, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    
+    let path = Path::new(&repository_path);
+    let library = agent_library::AgentLibraryParser::parse(path)
+        .map_err(|e| format!("Failed to parse agent library: {e}"))?;
+    agent_library::exporter::export_prompts(&library, path, &formats.unwrap_or_default(), dry_run.unwrap_or(false))
+        .map_err(|e| format!("Failed to export prompt files: {e:#}"))
+}
+
+#[tauri::command]
+async fn create_agent_library(
+    repository_path: String,
+    options: Option<agent_library::scaffold::ScaffoldOptions>,
+) -> Result<agent_library::scaffold::ScaffoldReport, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    let options = options.unwrap_or_default();
+    if let Some(template_dir) = &options.template_dir {
+        validate_path_security(&template_dir.to_string_lossy())?;
+    }
+    
+    agent_library::scaffold::create_library(Path::new(&repository_path), &options)
+        .map_err(|e| format!("Failed to create agent library: {e:#}"))
+}
+
+#[tauri::command]
+async fn create_endpoint(
+    repository_path: String,
+    endpoint: agent_library::McpEndpoint,
+    content: Option<String>,
+) -> Result<(), String> {
+    // セキュリティ: パス検証とコンテンツサイズ制限
+    validate_path_security(&repository_path)?;
+    if content.as_ref().is_some_and(|c| c.len() > 1024 * 1024) {
+        return Err("Content size exceeds maximum limit (1MB)".to_string());
+    }
+    
+    agent_library::endpoints::create_endpoint(Path::new(&repository_path), &endpoint, content.as_deref())
+        .map_err(|e| format!("Failed to create endpoint: {e:#}"))
+}
+
+#[tauri::command]
+async fn rename_endpoint(
+    repository_path: String,
+    id: String,
+    new_id: String,
+    keep_alias: Option<bool>,
+) -> Result<(), String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    
+    agent_library::endpoints::rename_endpoint(Path::new(&repository_path), &id, &new_id, keep_alias.unwrap_or(true))
+        .map_err(|e| format!("Failed to rename endpoint: {e:#}"))
+}
+
+#[tauri::command]
+async fn duplicate_endpoint(
+    repository_path: String,
+    id: String,
+    new_id: String,
+) -> Result<agent_library::McpEndpoint, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    
+    agent_library::endpoints::duplicate_endpoint(Path::new(&repository_path), &id, &new_id)
+        .map_err(|e| format!("Failed to duplicate endpoint: {e:#}"))
+}
+
+#[tauri::command]
+async fn reorder_endpoints(repository_path: String, order: Vec<String>) -> Result<(), String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    
+    agent_library::endpoints::reorder_endpoints(Path::new(&repository_path), &order)
+        .map_err(|e| format!("Failed to reorder endpoints: {e:#}"))
+}
+
+#[tauri::command]
+async fn delete_endpoint(repository_path: String, id: String, delete_files: Option<bool>) -> Result<(), String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    
+    agent_library::endpoints::delete_endpoint(Path::new(&repository_path), &id, delete_files.unwrap_or(false))
+        .map_err(|e| format!("Failed to delete endpoint: {e:#}"))
+}
+
+#[tauri::command]
+async fn list_history_snapshots(repository_path: String) -> Result<Vec<agent_library::history::Snapshot>, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    
+    agent_library::history::store()
+        .and_then(|store| store.list(Path::new(&repository_path)))
+        .map_err(|e| format!("Failed to list history: {e:#}"))
+}
+
+#[tauri::command]
+async fn diff_history_snapshot(
+    repository_path: String,
+    snapshot_id: String,
+) -> Result<agent_library::history::SnapshotDiff, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    
+    agent_library::history::store()
+        .and_then(|store| store.diff(Path::new(&repository_path), &snapshot_id))
+        .map_err(|e| format!("Failed to diff snapshot: {e:#}"))
+}
+
+#[tauri::command]
+async fn restore_history_snapshot(
+    repository_path: String,
+    snapshot_id: String,
+) -> Result<agent_library::history::Snapshot, String> {
+    // セキュリティ: パス検証
+    validate_path_security(&repository_path)?;
+    
+    agent_library::history::store()
+        .and_then(|store| store.restore(Path::new(&repository_path), &snapshot_id))
+        .map_err(|e| format!("Failed to restore snapshot: {e:#}"))
+}
+
+#[tauri::command]
+async fn find_repositories(
+    search_paths: Vec<String>,
+    options: Option<agent_library::SearchOptions>,
+) -> Result<Vec<agent_library::RepositoryInfo>, String> {
     // セキュリティ: 各パスを検証
     for path in &search_paths {
         validate_path_security(path)?;
@@ -79,13 +333,12 @@ async fn find_repositories(search_paths: Vec<String>) -> Result<Vec<String>, Str
     let paths: Vec<std::path::PathBuf> = search_paths.into_iter()
         .map(std::path::PathBuf::from)
         .collect();
+    let options = options.unwrap_or_default();
     
-    let repos = agent_library::AgentLibraryParser::find_repositories(&paths)
-        .map_err(|e| e.to_string())?;
-    
-    Ok(repos.into_iter()
-        .map(|p| p.to_string_lossy().to_string())
-        .collect())
+    // ライブラリの読み込みを伴うためブロッキングスレッドで探索する
+    tokio::task::spawn_blocking(move || agent_library::search::search_repositories(&paths, &options))
+        .await
+        .map_err(|e| e.to_string())
 }
 
 // MCP Server commands
@@ -162,7 +415,12 @@ async fn load_agent_library_to_mcp(repo_path: String) -> Result<String, String>
 
 // Repository-specific MCP server commands
 #[tauri::command]
-async fn start_repository_mcp_server(repository_id: String, repo_path: String, port: Option<u16>) -> Result<String, String> {
+async fn start_repository_mcp_server(
+    repository_id: String,
+    repo_path: String,
+    port: Option<u16>,
+    default_locale: Option<String>,
+) -> Result<String, String> {
     info!(repository_id = %repository_id, repo_path = %repo_path, "Starting MCP server for repository");
     
     // セキュリティ: パス検証
@@ -189,7 +447,7 @@ async fn start_repository_mcp_server(repository_id: String, repo_path: String, p
     info!(repository_id = %repository_id, prompts_count = library.prompts.len(), "Agent library parsed successfully");
     
     // Create MCP server state for this repository
-    let state = mcp::McpServerState::new();
+    let state = mcp::McpServerState::new().with_default_locale(default_locale);
     {
         let mut libraries = state.agent_libraries.write().await;
         libraries.push(library);
@@ -352,15 +610,20 @@ async fn get_watched_repositories() -> Result<Vec<String>, String> {
 }
 
 #[tauri::command]
-async fn save_prompt_file(repo_path: String, prompt_id: String, content: String) -> Result<(), String> {
-    use std::io::Write;
+async fn save_prompt_file(
+    repo_path: String,
+    prompt_id: String,
+    content: String,
+    expected: Option<agent_library::storage::ExpectedVersion>,
+) -> Result<agent_library::storage::FileVersion, agent_library::storage::SaveError> {
+    use agent_library::storage::SaveError;
     
     // セキュリティ: パス検証とコンテンツサイズ制限
-    validate_path_security(&repo_path)?;
+    validate_path_security(&repo_path).map_err(|message| SaveError::Failed { message })?;
     
     // コンテンツサイズ制限（1MB）
     if content.len() > 1024 * 1024 {
-        return Err("Content size exceeds maximum limit (1MB)".to_string());
+        return Err(SaveError::Failed { message: "Content size exceeds maximum limit (1MB)".to_string() });
     }
     
     info!(repo_path = %repo_path, prompt_id = %prompt_id, content_length = content.len(), "Saving prompt file");
@@ -370,7 +633,7 @@ async fn save_prompt_file(repo_path: String, prompt_id: String, content: String)
     let library = agent_library::AgentLibraryParser::parse(path)
         .map_err(|e| {
             error!(repo_path = %repo_path, error = %e, "Failed to parse agent library for prompt save");
-            format!("Failed to parse agent library: {e}")
+            SaveError::Failed { message: format!("Failed to parse agent library: {e}") }
         })?;
     
     // Find the prompt by ID
@@ -378,42 +641,78 @@ async fn save_prompt_file(repo_path: String, prompt_id: String, content: String)
         .find(|p| p.id == prompt_id)
         .ok_or_else(|| {
             warn!(prompt_id = %prompt_id, available_prompts = ?library.prompts.iter().map(|p| &p.id).collect::<Vec<_>>(), "Prompt not found");
-            format!("Prompt with ID '{prompt_id}' not found")
+            SaveError::Failed { message: format!("Prompt with ID '{prompt_id}' not found") }
         })?;
     
     // Get the prompt file path (prompt.file_path is already the full path)
     let prompt_file_path = &prompt.file_path;
     debug!(prompt_file_path = %prompt_file_path.display(), "Writing to prompt file");
     
-    // Write content to file
-    let mut file = std::fs::File::create(prompt_file_path)
+    // 一時ファイルに書き込んでから置き換える（読み込み後に変更されていた場合は競合）
+    let version = agent_library::storage::save(prompt_file_path, &content, &expected.unwrap_or_default())
         .map_err(|e| {
-            error!(prompt_file_path = %prompt_file_path.display(), error = %e, "Failed to create prompt file");
-            format!("Failed to create file {}: {}", prompt_file_path.display(), e)
-        })?;
-    
-    file.write_all(content.as_bytes())
-        .map_err(|e| {
-            error!(prompt_file_path = %prompt_file_path.display(), error = %e, "Failed to write to prompt file");
-            format!("Failed to write to file {}: {}", prompt_file_path.display(), e)
+            warn!(prompt_file_path = %prompt_file_path.display(), error = %e, "Failed to save prompt file");
+            e
         })?;
     
     info!(prompt_id = %prompt_id, prompt_file_path = %prompt_file_path.display(), "Prompt file saved successfully");
     
-    Ok(())
+    Ok(version)
 }
 
 #[tauri::command]
-async fn reload_agent_library(repository_id: String, repository_path: String) -> Result<String, String> {
-    info!(repository_id = %repository_id, repository_path = %repository_path, "Reloading agent library");
+async fn reload_agent_library(
+    repository_id: String,
+    repository_path: String,
+    changed_path: Option<String>,
+) -> Result<String, String> {
+    info!(repository_id = %repository_id, repository_path = %repository_path, changed_path = ?changed_path, "Reloading agent library");
     
     // セキュリティ: パス検証
     validate_path_security(&repository_path)?;
+    if let Some(changed_path) = &changed_path {
+        validate_path_security(changed_path)?;
+    }
+    
+    let state_option = {
+        let servers = MCP_SERVERS.lock().unwrap();
+        servers.get(&repository_id).map(|instance| instance.state.clone())
+    }; // MutexGuardをここでdrop
+    
+    // 実行中のMCPサーバーがあり、変更されたファイルが分かる場合は部分的に更新する
+    if let (Some(state), Some(changed_path)) = (&state_option, &changed_path) {
+        let mut libraries = state.agent_libraries.write().await;
+        if let Some(library) = libraries.first_mut() {
+            match agent_library::AgentLibraryParser::apply_change(library, std::path::Path::new(changed_path)) {
+                Ok(update) => {
+                    mcp::server::clear_response_cache();
+                    return Ok(match update {
+                        agent_library::LibraryUpdate::Unchanged => format!(
+                            "No prompts affected by {changed_path} in repository '{repository_id}'"
+                        ),
+                        agent_library::LibraryUpdate::Prompts(ids) => format!(
+                            "Updated prompts [{}] for repository '{}'",
+                            ids.join(", "),
+                            repository_id
+                        ),
+                        agent_library::LibraryUpdate::Reloaded => format!(
+                            "Reloaded {} prompts and {} endpoints for repository '{}'",
+                            library.prompts.len(),
+                            library.index.mcp_endpoints.len(),
+                            repository_id
+                        ),
+                    });
+                }
+                Err(e) => {
+                    // 部分更新に失敗した場合はライブラリ全体を読み直す
+                    warn!(repository_id = %repository_id, changed_path = %changed_path, error = %e, "Incremental reload failed, falling back to full reload");
+                }
+            }
+        }
+    }
     
     // キャッシュを無効化
-    let _agent_lib_path = std::path::Path::new(&repository_path).join(".agent_library");
-    // Note: agent_library::parser::AGENT_LIBRARY_CACHE.invalidate(&agent_lib_path);
-    // TODO: キャッシュ無効化APIを公開する必要がある
+    agent_library::AgentLibraryParser::invalidate(std::path::Path::new(&repository_path));
     
     // agent_library を再読み込みしてMCPサーバーの状態を更新
     let path = std::path::Path::new(&repository_path);
@@ -423,17 +722,12 @@ async fn reload_agent_library(repository_id: String, repository_path: String) ->
             format!("Failed to reload agent library: {e}")
         })?;
     
-    // 実行中のMCPサーバーがあれば更新
-    let state_option = {
-        let servers = MCP_SERVERS.lock().unwrap();
-        servers.get(&repository_id).map(|instance| instance.state.clone())
-    }; // MutexGuardをここでdrop
-    
     if let Some(state) = state_option {
         // MCPサーバーの状態を更新
         let mut libraries = state.agent_libraries.write().await;
         libraries.clear();
         libraries.push(library.clone());
+        mcp::server::clear_response_cache();
         
         Ok(format!(
             "Reloaded {} prompts and {} endpoints for repository '{}'",
@@ -574,7 +868,13 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
+            use tauri::Manager;
             info!("Setting up Tauri application");
+            // 変更前の内容を記録する履歴の保存先
+            match app.handle().path().app_data_dir() {
+                Ok(data_dir) => agent_library::history::set_root(data_dir.join("history")),
+                Err(e) => warn!(error = %e, "Failed to get app data directory, history is disabled"),
+            }
             // ファイル監視マネージャーを初期化
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
@@ -588,6 +888,25 @@ pub fn run() {
             select_directory,
             parse_agent_library,
             validate_agent_library,
+            get_agent_library_diagnostics,
+            get_prompt_summary,
+            get_library_taxonomy,
+            query_prompts,
+            export_agent_library,
+            inspect_library_bundle,
+            import_agent_library,
+            import_prompt_files,
+            export_prompt_files,
+            create_agent_library,
+            create_endpoint,
+            rename_endpoint,
+            duplicate_endpoint,
+            reorder_endpoints,
+            delete_endpoint,
+            list_history_snapshots,
+            diff_history_snapshot,
+            restore_history_snapshot,
+            lint_agent_library,
             find_repositories,
             start_mcp_server,
             load_agent_library_to_mcp,
@@ -10,8 +10,10 @@ use tokio::sync::RwLock;
 use std::collections::HashMap;
 // use once_cell::sync::Lazy; // 現在未使用
 
-use super::types::{JsonRpcRequest, JsonRpcResponse, JsonRpcError, McpPrompt, McpMessage, McpContent, McpResource};
-use crate::agent_library::AgentLibrary;
+use super::types::{JsonRpcRequest, JsonRpcResponse, JsonRpcError, McpPrompt, McpArgument, McpMessage, McpContent, McpResource};
+use crate::agent_library::resources::{self, ResourceContent};
+use crate::agent_library::{locale, template, AgentLibrary, Deprecation, Prompt, PromptQuery};
+use base64::Engine;
 
 // レスポンスキャッシュの実装（シンプルなHashMapベース）
 #[derive(Clone)]
@@ -21,6 +23,13 @@ struct CachedResponse {
     created_at: Instant,
 }
 
+// prompts/get の `_meta` でプロンプトの構造を要求するキー
+const SUMMARY_META_KEY: &str = "librarian/summary";
+// 非推奨のプロンプトの置き換え先などを `_meta` で返すキー
+const DEPRECATED_META_KEY: &str = "librarian/deprecated";
+// prompts/list の `_meta` で絞り込み条件（タグ・カテゴリ・トリガー）を指定するキー
+const FILTER_META_KEY: &str = "librarian/filter";
+
 type ResponseCacheEntry = (CachedResponse, Instant);
 static RESPONSE_CACHE: std::sync::LazyLock<Mutex<HashMap<String, ResponseCacheEntry>>> = std::sync::LazyLock::new(|| {
     Mutex::new(HashMap::new())
@@ -29,6 +38,8 @@ static RESPONSE_CACHE: std::sync::LazyLock<Mutex<HashMap<String, ResponseCacheEn
 #[derive(Clone)]
 pub struct McpServerState {
     pub agent_libraries: Arc<RwLock<Vec<AgentLibrary>>>,
+    // prompts/get で `locale` 引数が無い場合に使うロケール
+    pub default_locale: Option<String>,
 }
 
 impl Default for McpServerState {
@@ -41,8 +52,21 @@ impl McpServerState {
     #[must_use] pub fn new() -> Self {
         Self {
             agent_libraries: Arc::new(RwLock::new(Vec::new())),
+            default_locale: None,
         }
     }
+
+    #[must_use] pub fn with_default_locale(mut self, locale: Option<String>) -> Self {
+        self.default_locale = locale.filter(|l| !l.is_empty());
+        self
+    }
+}
+
+/// ライブラリの更新後に古い応答を返さないようレスポンスキャッシュを破棄する
+pub fn clear_response_cache() {
+    if let Ok(mut cache) = RESPONSE_CACHE.lock() {
+        cache.clear();
+    }
 }
 
 pub fn create_mcp_router() -> Router<McpServerState> {
@@ -71,7 +95,7 @@ async fn handle_jsonrpc(
     Json(request): Json<JsonRpcRequest>,
 ) -> Result<(HeaderMap, Json<JsonRpcResponse>), StatusCode> {
     let response = match request.method.as_str() {
-        "initialize" => handle_initialize(request.id, request.params).await,
+        "initialize" => handle_initialize(state, request.id, request.params).await,
         "initialized" => handle_initialized(request.id).await,
         "prompts/list" => handle_prompts_list(state, request.id, request.params).await,
         "prompts/get" => handle_prompts_get(state, request.id, request.params).await,
@@ -99,7 +123,27 @@ async fn handle_jsonrpc(
     Ok((headers, Json(response)))
 }
 
-async fn handle_initialize(id: Option<serde_json::Value>, _params: Option<serde_json::Value>) -> JsonRpcResponse {
+async fn handle_initialize(
+    state: McpServerState,
+    id: Option<serde_json::Value>,
+    _params: Option<serde_json::Value>,
+) -> JsonRpcResponse {
+    // serverInfo にはライブラリのメタデータを使い、未指定か不正な場合はアプリの名前とバージョンにフォールバック
+    let (server_name, server_version) = {
+        let libraries = state.agent_libraries.read().await;
+        let index = libraries.first().map(|library| &library.index);
+        (
+            index
+                .and_then(|i| i.name.clone())
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or_else(|| "librarian".to_string()),
+            index
+                .and_then(|i| i.version.clone())
+                .filter(|version| semver::Version::parse(version).is_ok())
+                .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string()),
+        )
+    };
+
     JsonRpcResponse {
         jsonrpc: "2.0".to_string(),
         id,
@@ -110,8 +154,8 @@ async fn handle_initialize(id: Option<serde_json::Value>, _params: Option<serde_
                 "resources": {}
             },
             "serverInfo": {
-                "name": "librarian",
-                "version": "0.1.0"
+                "name": server_name,
+                "version": server_version
             }
         })),
         error: None,
@@ -130,10 +174,37 @@ async fn handle_initialized(id: Option<serde_json::Value>) -> JsonRpcResponse {
 async fn handle_prompts_list(
     state: McpServerState,
     id: Option<serde_json::Value>,
-    _params: Option<serde_json::Value>,
+    params: Option<serde_json::Value>,
 ) -> JsonRpcResponse {
-    // キャッシュキーを生成（状態のハッシュベース）
-    let cache_key = "prompts_list".to_string();
+    let query: PromptQuery = match params
+        .as_ref()
+        .and_then(|p| p.get("_meta"))
+        .and_then(|m| m.get(FILTER_META_KEY))
+    {
+        Some(filter) => match serde_json::from_value(filter.clone()) {
+            Ok(query) => query,
+            Err(e) => {
+                return JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id,
+                    result: None,
+                    error: Some(JsonRpcError {
+                        code: -32602,
+                        message: format!("Invalid params: {FILTER_META_KEY}: {e}"),
+                        data: None,
+                    }),
+                }
+            }
+        },
+        None => PromptQuery::default(),
+    };
+
+    // キャッシュキーを生成（既定のロケールと絞り込み条件ごと）
+    let cache_key = format!(
+        "prompts_list:{}:{}",
+        state.default_locale.as_deref().unwrap_or_default(),
+        serde_json::to_string(&query).unwrap_or_default()
+    );
     let cache_ttl = Duration::from_secs(30);
     
     // キャッシュから確認
@@ -157,12 +228,34 @@ async fn handle_prompts_list(
     let mut prompts = Vec::new();
 
     for library in libraries.iter() {
-        for prompt in &library.prompts {
+        for prompt in library.prompts.iter().filter(|p| query.matches(p)) {
+            // タイトルと説明は既定のロケールのバリアントを優先する
+            let variant = state
+                .default_locale
+                .as_deref()
+                .and_then(|l| locale::select(&prompt.variants, l));
+            let mut arguments: Vec<McpArgument> = prompt.arguments.iter().map(|argument| McpArgument {
+                name: argument.name.clone(),
+                description: argument.description.clone(),
+                required: Some(argument.required),
+            }).collect();
+            if !prompt.variants.is_empty() && !arguments.iter().any(|a| a.name == "locale") {
+                let locales: Vec<&str> = prompt.variants.iter().map(|v| v.locale.as_str()).collect();
+                arguments.push(McpArgument {
+                    name: "locale".to_string(),
+                    description: Some(format!("Locale of the prompt ({})", locales.join(", "))),
+                    required: Some(false),
+                });
+            }
+
             prompts.push(McpPrompt {
                 name: prompt.id.clone(),
-                title: Some(prompt.title.clone()),
-                description: Some(prompt.description.clone()),
-                arguments: vec![],
+                title: Some(variant.map_or(&prompt.title, |v| &v.title).clone()),
+                description: Some(describe(
+                    variant.map_or(&prompt.description, |v| &v.description),
+                    prompt.deprecated.as_ref(),
+                )),
+                arguments,
             });
         }
     }
@@ -211,24 +304,84 @@ async fn handle_prompts_get(
         }
     };
 
-    let libraries = state.agent_libraries.read().await;
-    for library in libraries.iter() {
-        if let Some(prompt) = library.prompts.iter().find(|p| p.id == name) {
-            let message = McpMessage {
-                role: "user".to_string(),
-                content: McpContent {
-                    content_type: "text".to_string(),
-                    text: prompt.content.clone(),
-                },
-            };
-
+    let provided = match parse_prompt_arguments(params.as_ref()) {
+        Ok(provided) => provided,
+        Err(message) => {
             return JsonRpcResponse {
                 jsonrpc: "2.0".to_string(),
                 id,
-                result: Some(serde_json::json!({ "messages": [message] })),
-                error: None,
-            };
+                result: None,
+                error: Some(JsonRpcError {
+                    code: -32602,
+                    message,
+                    data: None,
+                }),
+            }
+        }
+    };
+
+    let libraries = state.agent_libraries.read().await;
+    if let Some(prompt) = find_prompt(&libraries, &name) {
+        if prompt.id != name {
+            tracing::debug!("Resolved prompt alias '{name}' to '{}'", prompt.id);
+        }
+        let values = match template::resolve_arguments(&prompt.arguments, &provided) {
+            Ok(values) => values,
+            Err(missing) => {
+                return JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id,
+                    result: None,
+                    error: Some(JsonRpcError {
+                        code: -32602,
+                        message: format!("Invalid params: missing required arguments: {}", missing.join(", ")),
+                        data: Some(serde_json::json!({ "missing": missing })),
+                    }),
+                };
+            }
+        };
+
+        // `locale` 引数、サーバーの既定ロケールの順にバリアントを選び、無ければ基本のファイルを使う
+        let variant = provided
+            .get("locale")
+            .or(state.default_locale.as_ref())
+            .and_then(|l| locale::select(&prompt.variants, l));
+        let (prompt_messages, description, summary) = variant.map_or(
+            (&prompt.messages, &prompt.description, &prompt.summary),
+            |v| (&v.messages, &v.description, &v.summary),
+        );
+
+        // ロール区切りがないプロンプトも読み込み時に1つの user メッセージになっている
+        let messages: Vec<McpMessage> = prompt_messages.iter().map(|message| McpMessage {
+            role: message.role.clone(),
+            content: McpContent {
+                content_type: "text".to_string(),
+                text: template::render(&message.content, &values),
+            },
+        }).collect();
+
+        let description = describe(description, prompt.deprecated.as_ref());
+        let mut result = serde_json::json!({ "description": description, "messages": messages });
+        // 要求された場合は本文の構造を `_meta` に含める
+        let include_summary = params
+            .as_ref()
+            .and_then(|p| p.get("_meta"))
+            .and_then(|m| m.get(SUMMARY_META_KEY))
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false);
+        if include_summary {
+            result["_meta"][SUMMARY_META_KEY] = serde_json::json!(summary);
+        }
+        if let Some(deprecated) = &prompt.deprecated {
+            result["_meta"][DEPRECATED_META_KEY] = serde_json::json!(deprecated);
         }
+
+        return JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: Some(result),
+            error: None,
+        };
     }
 
     JsonRpcResponse {
@@ -243,6 +396,45 @@ async fn handle_prompts_get(
     }
 }
 
+// prompts/get の `arguments` を文字列のマップとして取り出す
+fn parse_prompt_arguments(params: Option<&serde_json::Value>) -> Result<HashMap<String, String>, String> {
+    let Some(arguments) = params.and_then(|p| p.get("arguments")) else {
+        return Ok(HashMap::new());
+    };
+
+    match arguments {
+        serde_json::Value::Null => Ok(HashMap::new()),
+        serde_json::Value::Object(map) => Ok(map
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                (key.clone(), value)
+            })
+            .collect()),
+        _ => Err("Invalid params: arguments must be an object".to_string()),
+    }
+}
+
+// ID で探し、見つからなければ別名で探す（別のライブラリの ID が別名より優先される）
+fn find_prompt<'a>(libraries: &'a [AgentLibrary], name: &str) -> Option<&'a Prompt> {
+    libraries
+        .iter()
+        .find_map(|library| library.prompts.iter().find(|p| p.id == name))
+        .or_else(|| libraries.iter().find_map(|library| library.find_prompt(name)))
+}
+
+// 非推奨のプロンプトは説明の先頭に案内を付ける
+fn describe(description: &str, deprecated: Option<&Deprecation>) -> String {
+    match deprecated {
+        Some(deprecated) if description.is_empty() => format!("[{}]", deprecated.notice()),
+        Some(deprecated) => format!("[{}] {description}", deprecated.notice()),
+        None => description.to_string(),
+    }
+}
+
 async fn handle_resources_list(
     state: McpServerState,
     id: Option<serde_json::Value>,
@@ -257,10 +449,24 @@ async fn handle_resources_list(
                 uri: format!("agent_library://{}", prompt.id),
                 name: prompt.id.clone(),
                 title: Some(prompt.title.clone()),
-                description: Some(prompt.description.clone()),
+                description: Some(describe(&prompt.description, prompt.deprecated.as_ref())),
                 mime_type: Some("text/markdown".to_string()),
             });
         }
+        // 添付ファイルとライブラリ全体のリソース
+        let files = library.prompts.iter().flat_map(|p| &p.attachments).chain(&library.resources);
+        for resource in files {
+            if resources.iter().any(|r: &McpResource| r.uri == resource.uri) {
+                continue;
+            }
+            resources.push(McpResource {
+                uri: resource.uri.clone(),
+                name: resource.name.clone(),
+                title: None,
+                description: resource.description.clone(),
+                mime_type: Some(resource.mime_type.clone()),
+            });
+        }
     }
 
     JsonRpcResponse {
@@ -292,23 +498,61 @@ async fn handle_resources_read(
         }
     };
 
-    if let Some(prompt_id) = uri.strip_prefix("agent_library://") {
+    if uri.starts_with(resources::RESOURCE_URI_PREFIX) {
         let libraries = state.agent_libraries.read().await;
-        for library in libraries.iter() {
-            if let Some(prompt) = library.prompts.iter().find(|p| p.id == prompt_id) {
-                return JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id,
-                    result: Some(serde_json::json!({
+        let resource = libraries
+            .iter()
+            .flat_map(|library| library.prompts.iter().flat_map(|p| &p.attachments).chain(&library.resources))
+            .find(|r| r.uri == uri);
+        if let Some(resource) = resource {
+            let (result, error) = match resources::read(resource) {
+                Ok(ResourceContent::Text(text)) => (
+                    Some(serde_json::json!({
+                        "contents": [{ "uri": &uri, "mimeType": &resource.mime_type, "text": text }]
+                    })),
+                    None,
+                ),
+                Ok(ResourceContent::Blob(bytes)) => (
+                    Some(serde_json::json!({
                         "contents": [{
                             "uri": &uri,
-                            "mimeType": "text/markdown",
-                            "text": prompt.content
+                            "mimeType": &resource.mime_type,
+                            "blob": base64::engine::general_purpose::STANDARD.encode(bytes)
                         }]
                     })),
-                    error: None,
-                };
-            }
+                    None,
+                ),
+                Err(e) => (
+                    None,
+                    Some(JsonRpcError {
+                        code: -32603,
+                        message: format!("Failed to read resource '{uri}': {e}"),
+                        data: None,
+                    }),
+                ),
+            };
+            return JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id,
+                result,
+                error,
+            };
+        }
+    } else if let Some(prompt_id) = uri.strip_prefix("agent_library://") {
+        let libraries = state.agent_libraries.read().await;
+        if let Some(prompt) = find_prompt(&libraries, prompt_id) {
+            return JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id,
+                result: Some(serde_json::json!({
+                    "contents": [{
+                        "uri": &uri,
+                        "mimeType": "text/markdown",
+                        "text": prompt.content
+                    }]
+                })),
+                error: None,
+            };
         }
     }
 
@@ -327,12 +571,19 @@ async fn handle_resources_read(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::agent_library::{AgentLibrary, AgentIndex, Prompt};
+    use crate::agent_library::{AgentLibrary, AgentIndex, Prompt, PromptArgument, PromptMessage, PromptVariant, Taxonomy};
     use std::path::PathBuf;
 
+    // 読み込み時と同じように本文をメッセージにする
+    fn user_messages(content: &str) -> Vec<PromptMessage> {
+        crate::agent_library::messages::split_messages(content).unwrap()
+    }
+
     fn create_test_agent_library() -> AgentLibrary {
         let index = AgentIndex {
-            mcp_endpoints: vec![],
+            name: Some("Test Library".to_string()),
+            version: Some("2.1.0".to_string()),
+            ..Default::default()
         };
 
         let prompt = Prompt {
@@ -340,19 +591,23 @@ mod tests {
             title: "Test Prompt".to_string(),
             description: "Test Description".to_string(),
             content: "Test prompt content".to_string(),
+            messages: user_messages("Test prompt content"),
             file_path: PathBuf::from("/test/prompt.md"),
+            ..Default::default()
         };
 
         AgentLibrary {
             index,
             base_path: PathBuf::from("/test/.agent_library"),
             prompts: vec![prompt],
+            resources: Vec::new(),
+            taxonomy: Taxonomy::default(),
         }
     }
 
     #[tokio::test]
     async fn test_handle_initialize() {
-        let response = handle_initialize(Some(serde_json::Value::from(1)), None).await;
+        let response = handle_initialize(McpServerState::new(), Some(serde_json::Value::from(1)), None).await;
         
         assert_eq!(response.jsonrpc, "2.0");
         assert_eq!(response.id, Some(serde_json::Value::from(1)));
@@ -363,9 +618,30 @@ mod tests {
             assert_eq!(result["protocolVersion"], "2025-06-18");
             assert!(result["capabilities"]["prompts"].is_object());
             assert!(result["capabilities"]["resources"].is_object());
+            assert_eq!(result["serverInfo"]["name"], "librarian");
+            assert_eq!(result["serverInfo"]["version"], env!("CARGO_PKG_VERSION"));
         }
     }
 
+    #[tokio::test]
+    async fn test_handle_initialize_uses_library_metadata() {
+        let state = McpServerState::new();
+        state.agent_libraries.write().await.push(create_test_agent_library());
+
+        let response = handle_initialize(state, Some(serde_json::Value::from(1)), None).await;
+        let result = response.result.unwrap();
+        assert_eq!(result["serverInfo"]["name"], "Test Library");
+        assert_eq!(result["serverInfo"]["version"], "2.1.0");
+
+        // セマンティックバージョンでない場合はアプリのバージョンを使う
+        let state = McpServerState::new();
+        let mut library = create_test_agent_library();
+        library.index.version = Some("1.0".to_string());
+        state.agent_libraries.write().await.push(library);
+        let response = handle_initialize(state, Some(serde_json::Value::from(1)), None).await;
+        assert_eq!(response.result.unwrap()["serverInfo"]["version"], env!("CARGO_PKG_VERSION"));
+    }
+
     #[tokio::test]
     async fn test_handle_initialized() {
         let response = handle_initialized(Some(serde_json::Value::from(1))).await;
@@ -402,6 +678,40 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_handle_prompts_list_filters() {
+        let mut library = create_templated_agent_library();
+        library.prompts[0].tags = vec!["quality".to_string()];
+        library.prompts[0].category = Some("development/review".to_string());
+        let mut other = library.prompts[0].clone();
+        other.id = "commit".to_string();
+        other.category = Some("development/git".to_string());
+        library.prompts.push(other);
+        let state = McpServerState::new();
+        state.agent_libraries.write().await.push(library);
+
+        let names = |response: JsonRpcResponse| -> Vec<String> {
+            response.result.unwrap()["prompts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let params = serde_json::json!({ "_meta": { "librarian/filter": { "category": "development/review" } } });
+        let response = handle_prompts_list(state.clone(), Some(serde_json::Value::from(1)), Some(params)).await;
+        assert_eq!(names(response), vec!["review"]);
+
+        let params = serde_json::json!({ "_meta": { "librarian/filter": { "category": "development", "tags": ["quality"] } } });
+        let response = handle_prompts_list(state.clone(), Some(serde_json::Value::from(2)), Some(params)).await;
+        assert_eq!(names(response), vec!["review", "commit"]);
+
+        let params = serde_json::json!({ "_meta": { "librarian/filter": { "tags": "quality" } } });
+        let response = handle_prompts_list(state, Some(serde_json::Value::from(3)), Some(params)).await;
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
     #[tokio::test]
     async fn test_handle_prompts_get_valid() {
         let state = McpServerState::new();
@@ -467,6 +777,233 @@ mod tests {
         }
     }
 
+    fn create_templated_agent_library() -> AgentLibrary {
+        let prompt = Prompt {
+            id: "review".to_string(),
+            title: "Review".to_string(),
+            description: "Review a file".to_string(),
+            content: "Review {{file}} written in {{ language }}.".to_string(),
+            messages: user_messages("Review {{file}} written in {{ language }}."),
+            file_path: PathBuf::from("/test/review.md"),
+            arguments: vec![
+                PromptArgument {
+                    name: "file".to_string(),
+                    description: Some("File to review".to_string()),
+                    required: true,
+                    default: None,
+                },
+                PromptArgument {
+                    name: "language".to_string(),
+                    description: None,
+                    required: false,
+                    default: Some("Rust".to_string()),
+                },
+            ],
+            ..Default::default()
+        };
+
+        AgentLibrary {
+            index: AgentIndex::default(),
+            base_path: PathBuf::from("/test/.agent_library"),
+            prompts: vec![prompt],
+            resources: Vec::new(),
+            taxonomy: Taxonomy::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_prompts_get_substitutes_arguments() {
+        let state = McpServerState::new();
+        state.agent_libraries.write().await.push(create_templated_agent_library());
+
+        let params = serde_json::json!({ "name": "review", "arguments": { "file": "main.rs" } });
+        let response = handle_prompts_get(state, Some(serde_json::Value::from(1)), Some(params)).await;
+
+        assert!(response.error.is_none());
+        let result = response.result.unwrap();
+        assert_eq!(result["messages"][0]["content"]["text"], "Review main.rs written in Rust.");
+    }
+
+    #[tokio::test]
+    async fn test_handle_prompts_get_renders_blocks() {
+        let mut library = create_templated_agent_library();
+        library.prompts[0].messages =
+            user_messages("Review:\n{{#each file}}\n- {{this}}\n{{/each}}\n{{#if focus}}\nFocus on {{focus}}.\n{{/if}}");
+        let state = McpServerState::new();
+        state.agent_libraries.write().await.push(library);
+
+        let params = serde_json::json!({ "name": "review", "arguments": { "file": "a.rs\nb.rs" } });
+        let response = handle_prompts_get(state, Some(serde_json::Value::from(1)), Some(params)).await;
+
+        let result = response.result.unwrap();
+        assert_eq!(result["messages"][0]["content"]["text"], "Review:\n- a.rs\n- b.rs\n");
+    }
+
+    #[tokio::test]
+    async fn test_handle_prompts_get_multiple_messages() {
+        let mut library = create_templated_agent_library();
+        library.prompts[0].messages = vec![
+            PromptMessage {
+                role: "user".to_string(),
+                content: "Review {{file}}".to_string(),
+            },
+            PromptMessage {
+                role: "assistant".to_string(),
+                content: "Findings for {{file}}:".to_string(),
+            },
+        ];
+
+        let state = McpServerState::new();
+        state.agent_libraries.write().await.push(library);
+
+        let params = serde_json::json!({ "name": "review", "arguments": { "file": "lib.rs" } });
+        let response = handle_prompts_get(state, Some(serde_json::Value::from(1)), Some(params)).await;
+
+        let result = response.result.unwrap();
+        let messages = result["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["role"], "user");
+        assert_eq!(messages[0]["content"]["text"], "Review lib.rs");
+        assert_eq!(messages[1]["role"], "assistant");
+        assert_eq!(messages[1]["content"]["text"], "Findings for lib.rs:");
+    }
+
+    #[tokio::test]
+    async fn test_handle_prompts_get_selects_locale_variant() {
+        let mut library = create_templated_agent_library();
+        library.prompts[0].variants = vec![PromptVariant {
+            locale: "ja".to_string(),
+            title: "レビュー".to_string(),
+            description: "ファイルをレビューする".to_string(),
+            content: "{{file}} をレビューしてください。".to_string(),
+            messages: user_messages("{{file}} をレビューしてください。"),
+            ..Default::default()
+        }];
+        let state = McpServerState::new();
+        state.agent_libraries.write().await.push(library);
+
+        // `locale` 引数で選ぶ（地域付きのタグは言語で一致させる）
+        let params = serde_json::json!({ "name": "review", "arguments": { "file": "main.rs", "locale": "ja-JP" } });
+        let response = handle_prompts_get(state.clone(), Some(serde_json::Value::from(1)), Some(params)).await;
+        let result = response.result.unwrap();
+        assert_eq!(result["messages"][0]["content"]["text"], "main.rs をレビューしてください。");
+        assert_eq!(result["description"], "ファイルをレビューする");
+
+        // 一致するバリアントが無い場合は基本のファイルを使う
+        let params = serde_json::json!({ "name": "review", "arguments": { "file": "main.rs", "locale": "fr" } });
+        let response = handle_prompts_get(state.clone(), Some(serde_json::Value::from(2)), Some(params)).await;
+        assert_eq!(response.result.unwrap()["messages"][0]["content"]["text"], "Review main.rs written in Rust.");
+
+        // サーバーの既定ロケール
+        let state = state.with_default_locale(Some("ja".to_string()));
+        let params = serde_json::json!({ "name": "review", "arguments": { "file": "main.rs" } });
+        let response = handle_prompts_get(state, Some(serde_json::Value::from(3)), Some(params)).await;
+        assert_eq!(response.result.unwrap()["messages"][0]["content"]["text"], "main.rs をレビューしてください。");
+    }
+
+    #[tokio::test]
+    async fn test_handle_prompts_get_summary_meta() {
+        let mut library = create_templated_agent_library();
+        library.prompts[0].summary = crate::agent_library::markdown::summarize("# Review\n\nSee [guide](guide.md).");
+        let state = McpServerState::new();
+        state.agent_libraries.write().await.push(library);
+
+        let params = serde_json::json!({ "name": "review", "arguments": { "file": "main.rs" } });
+        let response = handle_prompts_get(state.clone(), Some(serde_json::Value::from(1)), Some(params)).await;
+        assert!(response.result.unwrap().get("_meta").is_none());
+
+        let params = serde_json::json!({
+            "name": "review",
+            "arguments": { "file": "main.rs" },
+            "_meta": { "librarian/summary": true }
+        });
+        let response = handle_prompts_get(state, Some(serde_json::Value::from(2)), Some(params)).await;
+        let summary = &response.result.unwrap()["_meta"]["librarian/summary"];
+        assert_eq!(summary["headings"][0]["text"], "Review");
+        assert_eq!(summary["links"][0]["url"], "guide.md");
+    }
+
+    #[tokio::test]
+    async fn test_handle_resources_read_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("style.md"), "# Style").unwrap();
+        std::fs::write(root.join("logo.png"), [0x89, b'P', b'N', b'G', 0]).unwrap();
+
+        let mut library = create_templated_agent_library();
+        let entry = |path: &str| crate::agent_library::ResourceEntry {
+            path: path.to_string(),
+            ..Default::default()
+        };
+        library.prompts[0].attachments = resources::resolve(root, &[entry("style.md")], Some("review"));
+        library.resources = resources::resolve(root, &[entry("logo.png")], None);
+        let state = McpServerState::new();
+        state.agent_libraries.write().await.push(library);
+
+        let response = handle_resources_list(state.clone(), Some(serde_json::Value::from(1)), None).await;
+        let listed = response.result.unwrap()["resources"].clone();
+        let uris: Vec<&str> = listed.as_array().unwrap().iter().map(|r| r["uri"].as_str().unwrap()).collect();
+        assert_eq!(
+            uris,
+            vec!["agent_library://review", "agent_library://files/style.md", "agent_library://files/logo.png"]
+        );
+        assert_eq!(listed[2]["mimeType"], "image/png");
+
+        let params = serde_json::json!({ "uri": "agent_library://files/style.md" });
+        let response = handle_resources_read(state.clone(), Some(serde_json::Value::from(2)), Some(params)).await;
+        let contents = &response.result.unwrap()["contents"][0];
+        assert_eq!(contents["mimeType"], "text/markdown");
+        assert_eq!(contents["text"], "# Style");
+
+        let params = serde_json::json!({ "uri": "agent_library://files/logo.png" });
+        let response = handle_resources_read(state.clone(), Some(serde_json::Value::from(3)), Some(params)).await;
+        let contents = &response.result.unwrap()["contents"][0];
+        assert_eq!(contents["blob"], "iVBORwA=");
+        assert!(contents.get("text").is_none());
+
+        let params = serde_json::json!({ "uri": "agent_library://files/missing.txt" });
+        let response = handle_resources_read(state, Some(serde_json::Value::from(4)), Some(params)).await;
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[tokio::test]
+    async fn test_handle_prompts_get_resolves_aliases() {
+        let mut library = create_templated_agent_library();
+        library.prompts[0].aliases = vec!["code_review".to_string()];
+        library.prompts[0].deprecated = Some(Deprecation {
+            replaced_by: Some("review_v2".to_string()),
+            message: None,
+        });
+        let state = McpServerState::new();
+        state.agent_libraries.write().await.push(library);
+
+        let params = serde_json::json!({ "name": "code_review", "arguments": { "file": "main.rs" } });
+        let response = handle_prompts_get(state.clone(), Some(serde_json::Value::from(1)), Some(params)).await;
+        let result = response.result.unwrap();
+        assert_eq!(result["messages"][0]["content"]["text"], "Review main.rs written in Rust.");
+        assert_eq!(result["description"], "[Deprecated: use 'review_v2' instead] Review a file");
+        assert_eq!(result["_meta"]["librarian/deprecated"]["replaced_by"], "review_v2");
+
+        let params = serde_json::json!({ "uri": "agent_library://code_review" });
+        let response = handle_resources_read(state, Some(serde_json::Value::from(2)), Some(params)).await;
+        assert_eq!(response.result.unwrap()["contents"][0]["text"], "Review {{file}} written in {{ language }}.");
+    }
+
+    #[tokio::test]
+    async fn test_handle_prompts_get_missing_required_arguments() {
+        let state = McpServerState::new();
+        state.agent_libraries.write().await.push(create_templated_agent_library());
+
+        let params = serde_json::json!({ "name": "review", "arguments": {} });
+        let response = handle_prompts_get(state, Some(serde_json::Value::from(1)), Some(params)).await;
+
+        assert!(response.result.is_none());
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32602);
+        assert!(error.message.contains("file"));
+        assert_eq!(error.data.unwrap()["missing"], serde_json::json!(["file"]));
+    }
+
     #[tokio::test]
     async fn test_response_cache() {
         let state = McpServerState::new();
@@ -29,6 +29,7 @@ describe('PromptsView Component', () => {
       description: 'First test prompt',
       content: '# Test Prompt 1\n\nThis is the first test prompt.',
       filePath: '/test/prompt1.md',
+      content_hash: 'hash1',
     },
     {
       id: 'prompt2',
@@ -100,7 +101,9 @@ describe('PromptsView Component', () => {
   });
 
   it('should save prompt when save button is clicked', async () => {
-    mockInvoke.mockResolvedValueOnce({ prompts: mockPrompts }).mockResolvedValueOnce(undefined); // For save operation
+    mockInvoke
+      .mockResolvedValueOnce({ prompts: mockPrompts })
+      .mockResolvedValueOnce({ hash: 'hash2', modified: null }); // For save operation
 
     render(PromptsView, { props: { repository: mockRepository } });
 
@@ -130,10 +133,46 @@ describe('PromptsView Component', () => {
         repoPath: '/test/path',
         promptId: 'prompt1',
         content: '# Modified Prompt\n\nThis is modified content.',
+        expected: { hash: 'hash1' },
       });
     });
   });
 
+  it('should show both versions when the file changed on disk', async () => {
+    mockInvoke.mockResolvedValueOnce({ prompts: mockPrompts }).mockRejectedValueOnce({
+      kind: 'conflict',
+      path: '/test/prompt1.md',
+      current: { hash: 'hash3', modified: null },
+      disk_content: '# Edited elsewhere',
+      your_content: '# Test Prompt 1\n\nThis is the first test prompt.',
+    });
+
+    render(PromptsView, { props: { repository: mockRepository } });
+
+    await waitFor(() => {
+      expect(screen.getByText('Test Prompt 1')).toBeInTheDocument();
+    });
+
+    await fireEvent.click(screen.getByText('Test Prompt 1'));
+    await fireEvent.click(screen.getByText('編集'));
+    await fireEvent.click(screen.getByText('保存'));
+
+    await waitFor(() => {
+      expect(screen.getByText('# Edited elsewhere')).toBeInTheDocument();
+    });
+
+    // 上書きする場合はディスク上の版を想定して保存し直す
+    mockInvoke.mockResolvedValueOnce({ hash: 'hash4', modified: null });
+    await fireEvent.click(screen.getByText('上書き保存'));
+
+    await waitFor(() => {
+      expect(mockInvoke).toHaveBeenLastCalledWith(
+        'save_prompt_file',
+        expect.objectContaining({ expected: { hash: 'hash3' } })
+      );
+    });
+  });
+
   it('should cancel edit mode when cancel button is clicked', async () => {
     render(PromptsView, { props: { repository: mockRepository } });
 
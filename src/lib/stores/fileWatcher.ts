@@ -64,7 +64,11 @@ export class FileWatcherAPI {
           if (enabled && this.shouldAutoReload(event.payload)) {
             // ファイルパスからリポジトリパスを推定
             const repositoryPath = event.payload.file_path.split('/.agent_library')[0];
-            this.reloadAgentLibrary(event.payload.repository_id, repositoryPath);
+            this.reloadAgentLibrary(
+              event.payload.repository_id,
+              repositoryPath,
+              event.payload.file_path
+            );
           }
         });
 
@@ -174,7 +178,11 @@ export class FileWatcherAPI {
   /**
    * agent_libraryを再読み込み
    */
-  static async reloadAgentLibrary(repositoryId: string, repositoryPath?: string): Promise<void> {
+  static async reloadAgentLibrary(
+    repositoryId: string,
+    repositoryPath?: string,
+    changedPath?: string
+  ): Promise<void> {
     try {
       let repoPath = repositoryPath;
 
@@ -192,6 +200,7 @@ export class FileWatcherAPI {
       const result = await invoke<string>('reload_agent_library', {
         repositoryId,
         repositoryPath: repoPath,
+        changedPath: changedPath ?? null,
       });
 
       console.log('Agent library reloaded:', result);